@@ -1,9 +1,51 @@
 // src/app.rs
-use crate::file_io::FileIO;
+use crate::browser;
+use crate::catalog::CatalogItem;
+use crate::clipboard;
+use crate::commands::{self, CommandError};
+use crate::config::Config;
+use crate::dates;
+use crate::editor;
+use crate::file_io::{next_note_id, AppData, FileIO, Session};
 use crate::note::Note;
-use crate::todo::Todo;
-use chrono::Local;
+use crate::search::{self, ItemRef, SearchResult};
+use crate::sort::{self, SortKey};
+use crate::tags::{normalize_tag, TagFilter};
+use crate::keymap::Keymap;
+use crate::links;
+use crate::theme::{Theme, THEME_NAMES};
+use crate::todo::{Recurrence, Severity, Todo, TodoFilter};
+use crate::undo::{EditKind, History, Snapshot, TextHistory, TextState};
+use chrono::{DateTime, Days, Local, Months, NaiveDate};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::io;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// How many structural changes `u`/`:undo` can step back through.
+const STRUCTURAL_UNDO_LIMIT: usize = 20;
+
+/// How many text edits Ctrl+Z can step back through per open item.
+const TEXT_UNDO_LIMIT: usize = 200;
+
+/// Entries in the main menu: Notes, Todos, Catalog, Help.
+const MENU_ITEMS: usize = 4;
+
+/// Range and step, in percent, for the list pane width Ctrl+H/Ctrl+L set.
+const LIST_WIDTH_MIN: u16 = 15;
+const LIST_WIDTH_MAX: u16 = 60;
+const LIST_WIDTH_STEP: u16 = 5;
+
+/// Executed commands kept for Up/Down recall, across sessions.
+const COMMAND_HISTORY_LIMIT: usize = 100;
+
+/// Commands whose changes `u`/`:undo` can revert as a whole.
+const STRUCTURAL_COMMANDS: &[&str] = &[
+    "del", "archive", "unarchive", "purge", "done", "sort", "reflow", "trim", "trim-all",
+    "fix-dates", "import", "import-md", "dedup",
+];
 
 pub enum AppMode {
     MainMenu,
@@ -12,11 +54,36 @@ pub enum AppMode {
     Editing,
     Help,
     Renaming,
+    Confirm,
+    SeverityPicker,
+    Inspect,
+    Search,
+    Restore,
+    Goto,
+    Password,
+    QuitPrompt,
+    DatePicker,
+}
+
+/// What the passphrase typed in `AppMode::Password` is for.
+pub enum PasswordStep {
+    /// Decrypting an encrypted `data.json` at startup.
+    Unlock,
+    /// Choosing a new passphrase, then typing it again to confirm.
+    Choose,
+    Confirm(String),
+}
+
+/// Bulk actions waiting for a y/n answer in `AppMode::Confirm`.
+pub enum PendingAction {
+    ArchiveDuplicateNotes(Vec<usize>),
+    ArchiveDuplicateTodos(Vec<usize>),
 }
 
 pub enum AppSection {
     Notes,
     Todos,
+    Catalog,
     Help,
 }
 
@@ -24,91 +91,471 @@ pub struct App {
     pub section: AppSection,
     pub notes: Vec<Note>,
     pub todos: Vec<Todo>,
+    /// Bookmarked links shown in the catalog section.
+    pub catalogs: Vec<CatalogItem>,
     pub selected_note: Option<usize>,
     pub selected_todo: Option<usize>,
+    pub selected_catalog: Option<usize>,
     pub current_note: Option<usize>,
     pub current_todo: Option<usize>,
+    pub current_catalog: Option<usize>,
     pub selected_menu_item: usize,
     pub mode: AppMode,
     pub command_buffer: String,
+    /// Executed commands, oldest first, and the entry Up/Down is showing
+    /// along with the text typed before browsing started.
+    pub command_history: Vec<String>,
+    pub history_index: Option<usize>,
+    pub history_draft: String,
+    /// Candidates Tab is cycling through and the one currently shown.
+    pub completions: Vec<&'static str>,
+    pub completion_index: usize,
+    /// First key of a two-key Normal-mode sequence such as `dd`.
+    pub pending_key: Option<char>,
     pub status_message: Option<String>,
     pub file_io: FileIO,
+    pub config: Config,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// Width of the list pane beside the editor, in percent.
+    pub list_width: u16,
     pub unsaved_changes: bool,
+    pub should_quit: bool,
+    pub pending_action: Option<PendingAction>,
+    /// Mode to go back to when the unsaved-changes quit prompt is cancelled.
+    pub mode_before_quit: Option<AppMode>,
+    /// Set when command mode was entered from Editing (Ctrl+O), so the
+    /// editor reopens once the command has run.
+    pub mode_before_command: Option<AppMode>,
+    pub zen: bool,
+    /// Show the open note as rendered Markdown while not editing.
+    pub preview: bool,
+    /// Completion filter applied to the todo list by `:filter`.
+    pub todo_filter: TodoFilter,
+    /// Tags set by `:tagged` / `:tagged-any`; only matching items are
+    /// listed.
+    pub tag_filter: Option<TagFilter>,
+    /// `:archived` lists archived notes and todos instead of live ones.
+    pub show_archived: bool,
+    /// Byte offset of the editing caret within the open item's content.
+    pub cursor_pos: usize,
+    /// Column Up/Down aim for, kept across short lines until the cursor
+    /// moves horizontally or the text changes.
+    pub preferred_column: Option<usize>,
+    /// Highlighted row in the severity picker popup.
+    pub severity_choice: usize,
+    /// Highlighted day in the due-date calendar.
+    pub date_choice: NaiveDate,
+    /// Pretty JSON shown by `:inspect`, and how far it is scrolled.
+    pub inspect_text: String,
+    pub inspect_scroll: u16,
+    pub structural_history: History,
+    /// Edit history of the item open in the editor; cleared when another
+    /// item is opened.
+    pub text_history: TextHistory,
+    /// Last `:find` query, its matches, and the highlighted result row.
+    pub search_query: String,
+    pub search_results: Vec<SearchResult>,
+    pub selected_result: usize,
+    /// Text typed into the go-to picker, the notes and todos whose titles
+    /// fuzzily match it (best first), and the highlighted row.
+    pub goto_query: String,
+    pub goto_matches: Vec<ItemRef>,
+    pub selected_goto: usize,
+    /// First visible row of each list, kept between frames so the view
+    /// only scrolls once the selection moves past its edge.
+    pub notes_list_offset: Cell<usize>,
+    pub todos_list_offset: Cell<usize>,
+    pub catalogs_list_offset: Cell<usize>,
+    /// First visible column of an unwrapped note, kept between frames like
+    /// the list offsets.
+    pub editor_hscroll: Cell<usize>,
+    /// Backups offered by `:restore`, newest first, and the highlighted row.
+    pub backups: Vec<PathBuf>,
+    pub selected_backup: usize,
+    /// When the data last changed while unsaved; autosave waits for the
+    /// configured `autosave_seconds` of quiet after this.
+    pub last_edit: Option<Instant>,
+    /// Local time shown in the status bar, refreshed on every tick.
+    pub clock: String,
+    /// The data file is encrypted and hasn't been unlocked yet; saving is
+    /// refused so the empty placeholder can't overwrite it.
+    pub locked: bool,
+    /// Passphrase being typed (never displayed) and what it is for.
+    pub password_buffer: String,
+    pub password_step: PasswordStep,
 }
 
 impl App {
     pub fn new() -> Result<Self, io::Error> {
         let file_io = FileIO::new()?;
-        let (notes, todos) = file_io.load_data()?;
-        Ok(App {
+        crate::logger::init(file_io.data_dir());
+        Self::with_file_io(file_io)
+    }
+
+    /// Loads everything from `file_io`'s data directory.
+    pub(crate) fn with_file_io(file_io: FileIO) -> Result<Self, io::Error> {
+        let mut status_message = None;
+        let mut locked = false;
+        let data = match file_io.load_data() {
+            Ok(data) => data,
+            // Nothing is loaded or saved until the passphrase is entered.
+            Err(err) if err.kind() == io::ErrorKind::PermissionDenied => {
+                locked = true;
+                status_message = Some("Enter the passphrase for data.json".to_string());
+                AppData::default()
+            }
+            // A corrupt file shouldn't lock the user out: set it aside and
+            // start empty instead.
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                log::error!("data file is corrupt: {}", err);
+                let moved = file_io.quarantine_data()?;
+                status_message = Some(format!(
+                    "data.json could not be read ({}); moved to {} and started empty",
+                    err,
+                    moved.display()
+                ));
+                AppData::default()
+            }
+            Err(err) => {
+                log::error!("loading data failed: {}", err);
+                return Err(err);
+            }
+        };
+        log::info!(
+            "loaded {} notes, {} todos and {} catalog entries",
+            data.notes.len(),
+            data.todos.len(),
+            data.catalogs.len()
+        );
+        let config = file_io.load_config();
+        let mut command_history = file_io.load_command_history();
+        let excess = command_history.len().saturating_sub(COMMAND_HISTORY_LIMIT);
+        command_history.drain(..excess);
+        let theme = Theme::from_config(&config);
+        let keymap = Keymap::new(&config.keymap);
+        let list_width = config.list_width_percent.clamp(LIST_WIDTH_MIN, LIST_WIDTH_MAX);
+        let mut app = App {
             section: AppSection::Notes,
-            notes,
-            todos,
+            notes: data.notes,
+            todos: data.todos,
+            catalogs: data.catalogs,
             selected_note: None,
             selected_todo: None,
+            selected_catalog: None,
             current_note: None,
             current_todo: None,
+            current_catalog: None,
             selected_menu_item: 0,
             mode: AppMode::MainMenu,
             command_buffer: String::new(),
-            status_message: None,
+            command_history,
+            history_index: None,
+            history_draft: String::new(),
+            completions: Vec::new(),
+            completion_index: 0,
+            status_message,
             file_io,
+            config,
+            theme,
+            list_width,
+            keymap,
             unsaved_changes: false,
-        })
+            should_quit: false,
+            pending_action: None,
+            mode_before_quit: None,
+            mode_before_command: None,
+            zen: false,
+            pending_key: None,
+            show_archived: false,
+            preview: false,
+            todo_filter: TodoFilter::All,
+            tag_filter: None,
+            cursor_pos: 0,
+            preferred_column: None,
+            severity_choice: 0,
+            date_choice: Local::now().date_naive(),
+            inspect_text: String::new(),
+            inspect_scroll: 0,
+            structural_history: History::new(STRUCTURAL_UNDO_LIMIT),
+            text_history: TextHistory::new(TEXT_UNDO_LIMIT),
+            search_query: String::new(),
+            search_results: Vec::new(),
+            selected_result: 0,
+            goto_query: String::new(),
+            goto_matches: Vec::new(),
+            selected_goto: 0,
+            notes_list_offset: Cell::new(0),
+            todos_list_offset: Cell::new(0),
+            catalogs_list_offset: Cell::new(0),
+            editor_hscroll: Cell::new(0),
+            backups: Vec::new(),
+            selected_backup: 0,
+            last_edit: None,
+            clock: Self::current_clock(),
+            locked,
+            password_buffer: String::new(),
+            password_step: PasswordStep::Unlock,
+        };
+        if !app.locked {
+            app.rename_duplicate_titles_on_load();
+        }
+        app.apply_saved_sorts();
+        if !app.locked {
+            app.restore_session();
+        }
+        if app.locked {
+            app.mode = AppMode::Password;
+        } else if app.config.encrypt && !app.file_io.is_encrypting() {
+            app.start_choosing_passphrase();
+        }
+        Ok(app)
+    }
+
+    /// Reopens the section and selection saved by `save_session`. Saved
+    /// indices past the end of a list, or pointing at hidden items, move to
+    /// the nearest visible item.
+    fn restore_session(&mut self) {
+        let session = self.file_io.load_session();
+        self.selected_note = session.selected_note;
+        self.selected_todo = session.selected_todo;
+        self.selected_catalog = session.selected_catalog;
+        self.reselect_visible_note();
+        self.reselect_visible_todo();
+        let order = self.catalog_display_order();
+        Self::reselect_visible(&order, &mut self.selected_catalog, &mut self.current_catalog);
+
+        let section = match session.section.as_deref() {
+            Some("notes") => AppSection::Notes,
+            Some("todos") => AppSection::Todos,
+            Some("catalog") => AppSection::Catalog,
+            _ => return,
+        };
+        self.section = section;
+        self.mode = AppMode::Normal;
+    }
+
+    /// Records the current section and selections for the next launch.
+    pub fn save_session(&self) {
+        let section = match (&self.mode, &self.section) {
+            (AppMode::MainMenu | AppMode::Password, _) => None,
+            (_, AppSection::Notes) => Some("notes"),
+            (_, AppSection::Todos) => Some("todos"),
+            (_, AppSection::Catalog) => Some("catalog"),
+            (_, AppSection::Help) => None,
+        };
+        let session = Session {
+            section: section.map(str::to_string),
+            selected_note: self.selected_note,
+            selected_todo: self.selected_todo,
+            selected_catalog: self.selected_catalog,
+        };
+        if let Err(err) = self.file_io.save_session(&session) {
+            log::warn!("saving session failed: {}", err);
+        }
+    }
+
+    pub fn on_tick(&mut self) {
+        self.clock = Self::current_clock();
+
+        if let Some(delay) = self.config.archive_delay() {
+            self.archive_completed_todos(delay);
+        }
+
+        if !self.unsaved_changes {
+            self.last_edit = None;
+        } else {
+            let last_edit = *self.last_edit.get_or_insert_with(Instant::now);
+            if self
+                .config
+                .autosave_interval()
+                .is_some_and(|interval| last_edit.elapsed() >= interval)
+            {
+                self.autosave();
+            }
+        }
+    }
+
+    fn current_clock() -> String {
+        Local::now().format("%H:%M").to_string()
+    }
+
+    /// Restarts the autosave countdown if the last input left changes
+    /// unsaved.
+    pub fn mark_edited(&mut self) {
+        if self.unsaved_changes {
+            self.last_edit = Some(Instant::now());
+        }
     }
 
-    pub fn on_tick(&mut self) {}
+    fn autosave(&mut self) {
+        match self.save_data() {
+            Ok(_) => {
+                log::info!("auto-saved {} notes and {} todos", self.notes.len(), self.todos.len());
+                self.status_message = Some("Auto-saved".to_string());
+                self.unsaved_changes = false;
+                self.last_edit = None;
+            }
+            Err(err) => {
+                log::error!("auto-save failed: {}", err);
+                self.status_message = Some(format!("Auto-save failed: {}", err));
+                // Wait another full interval rather than retrying every tick.
+                self.last_edit = Some(Instant::now());
+            }
+        }
+    }
 
     pub fn next_menu_item(&mut self) {
-        self.selected_menu_item = (self.selected_menu_item + 1) % 3;
+        self.selected_menu_item = (self.selected_menu_item + 1) % MENU_ITEMS;
     }
 
     pub fn previous_menu_item(&mut self) {
         self.selected_menu_item = if self.selected_menu_item == 0 {
-            2
+            MENU_ITEMS - 1
         } else {
             self.selected_menu_item - 1
         };
     }
 
+    /// Indices into `notes` in the order the list pane renders them.
+    /// Navigation walks this order so j/k always follow what is on screen.
+    /// Pinned notes come first, each group keeping its stored order.
+    pub fn note_display_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.notes.len())
+            .filter(|&i| {
+                let note = &self.notes[i];
+                note.archived == self.show_archived && self.passes_tag_filter(&note.tags)
+            })
+            .collect();
+        order.sort_by_key(|&i| !self.notes[i].pinned);
+        order
+    }
+
+    /// Indices into `todos` in the order the list pane renders them.
+    /// Archived todos (live ones in the `:archived` view) and those
+    /// excluded by `:filter` are hidden.
+    pub fn todo_display_order(&self) -> Vec<usize> {
+        (0..self.todos.len())
+            .filter(|&i| {
+                let todo = &self.todos[i];
+                todo.archived == self.show_archived
+                    && self.todo_filter.matches(todo)
+                    && self.passes_tag_filter(&todo.tags)
+            })
+            .collect()
+    }
+
+    /// Whether an item with `tags` survives the `:tagged` filter.
+    fn passes_tag_filter(&self, tags: &[String]) -> bool {
+        self.tag_filter.as_ref().is_none_or(|filter| filter.matches(tags))
+    }
+
+    /// Indices into `catalogs` in the order the list pane renders them.
+    pub fn catalog_display_order(&self) -> Vec<usize> {
+        (0..self.catalogs.len())
+            .filter(|&i| self.passes_tag_filter(&self.catalogs[i].tags))
+            .collect()
+    }
+
     pub fn next_note(&mut self) {
-        let next = match self.selected_note {
-            Some(i) if i >= self.notes.len() - 1 => Some(0),
-            Some(i) => Some(i + 1),
-            None if !self.notes.is_empty() => Some(0),
-            _ => None,
-        };
-        self.selected_note = next;
+        let order = self.note_display_order();
+        self.selected_note = Self::step_in_order(&order, self.selected_note, true);
+        Self::drop_stale_index(&mut self.current_note, self.notes.len());
     }
 
     pub fn previous_note(&mut self) {
-        let prev = match self.selected_note {
-            Some(0) => Some(self.notes.len() - 1),
-            Some(i) => Some(i - 1),
-            None if !self.notes.is_empty() => Some(self.notes.len() - 1),
-            _ => None,
-        };
-        self.selected_note = prev;
+        let order = self.note_display_order();
+        self.selected_note = Self::step_in_order(&order, self.selected_note, false);
+        Self::drop_stale_index(&mut self.current_note, self.notes.len());
     }
 
     pub fn next_todo(&mut self) {
-        let next = match self.selected_todo {
-            Some(i) if i >= self.todos.len() - 1 => Some(0),
-            Some(i) => Some(i + 1),
-            None if !self.todos.is_empty() => Some(0),
-            _ => None,
-        };
-        self.selected_todo = next;
+        let order = self.todo_display_order();
+        self.selected_todo = Self::step_in_order(&order, self.selected_todo, true);
+        Self::drop_stale_index(&mut self.current_todo, self.todos.len());
     }
 
     pub fn previous_todo(&mut self) {
-        let prev = match self.selected_todo {
-            Some(0) => Some(self.todos.len() - 1),
-            Some(i) => Some(i - 1),
-            None if !self.todos.is_empty() => Some(self.todos.len() - 1),
-            _ => None,
+        let order = self.todo_display_order();
+        self.selected_todo = Self::step_in_order(&order, self.selected_todo, false);
+        Self::drop_stale_index(&mut self.current_todo, self.todos.len());
+    }
+
+    pub fn next_catalog(&mut self) {
+        let order = self.catalog_display_order();
+        self.selected_catalog = Self::step_in_order(&order, self.selected_catalog, true);
+        Self::drop_stale_index(&mut self.current_catalog, self.catalogs.len());
+    }
+
+    pub fn previous_catalog(&mut self) {
+        let order = self.catalog_display_order();
+        self.selected_catalog = Self::step_in_order(&order, self.selected_catalog, false);
+        Self::drop_stale_index(&mut self.current_catalog, self.catalogs.len());
+    }
+
+    /// Selects the first (`last == false`) or last visible item of the
+    /// active section, leaving empty lists untouched.
+    pub fn jump_to_edge(&mut self, last: bool) {
+        let pick = |order: Vec<usize>| {
+            if last {
+                order.last().copied()
+            } else {
+                order.first().copied()
+            }
+        };
+        match self.section {
+            AppSection::Notes => {
+                if let Some(idx) = pick(self.note_display_order()) {
+                    self.selected_note = Some(idx);
+                }
+            }
+            AppSection::Todos => {
+                if let Some(idx) = pick(self.todo_display_order()) {
+                    self.selected_todo = Some(idx);
+                }
+            }
+            AppSection::Catalog => {
+                if let Some(idx) = pick(self.catalog_display_order()) {
+                    self.selected_catalog = Some(idx);
+                }
+            }
+            AppSection::Help => {}
+        }
+    }
+
+    /// The item after (or before) `selected` in `order`, wrapping around.
+    /// A selection that is missing from `order` (hidden, or past the end of
+    /// a list that shrank) restarts from the first or last item, and an
+    /// empty list clears it; nothing here indexes with a stale value.
+    fn step_in_order(order: &[usize], selected: Option<usize>, forward: bool) -> Option<usize> {
+        if order.is_empty() {
+            return None;
+        }
+        let position = selected.and_then(|i| order.iter().position(|&idx| idx == i));
+        let next = match position {
+            Some(p) if forward => (p + 1) % order.len(),
+            Some(p) => (p + order.len() - 1) % order.len(),
+            None if forward => 0,
+            None => order.len() - 1,
         };
-        self.selected_todo = prev;
+        Some(order[next])
+    }
+
+    /// Keeps an open-item index on the same item after `removed` is taken
+    /// out of its list, closing it if it was the one removed.
+    fn follow_removal(index: &mut Option<usize>, removed: usize) {
+        match *index {
+            Some(i) if i == removed => *index = None,
+            Some(i) if i > removed => *index = Some(i - 1),
+            _ => {}
+        }
+    }
+
+    /// Forgets an open-item index that no longer points into its list.
+    fn drop_stale_index(index: &mut Option<usize>, len: usize) {
+        if index.is_some_and(|i| i >= len) {
+            *index = None;
+        }
     }
 
     pub fn select_menu_item(&mut self) {
@@ -124,6 +571,11 @@ impl App {
                 self.status_message = Some("Todo section".to_string());
             }
             2 => {
+                self.section = AppSection::Catalog;
+                self.mode = AppMode::Normal;
+                self.status_message = Some("Catalog section".to_string());
+            }
+            3 => {
                 self.section = AppSection::Help;
                 self.mode = AppMode::Help;
                 self.status_message = Some("Help section".to_string());
@@ -132,31 +584,288 @@ impl App {
         }
     }
 
+    /// Remembers the command about to run, skipping blanks and immediate
+    /// repeats, and persists the history.
+    pub fn record_command(&mut self) {
+        self.reset_command_recall();
+        let command = self.command_buffer.trim();
+        if command.is_empty() || self.command_history.last().is_some_and(|c| c == command) {
+            return;
+        }
+
+        self.command_history.push(command.to_string());
+        if self.command_history.len() > COMMAND_HISTORY_LIMIT {
+            self.command_history.remove(0);
+        }
+        if let Err(err) = self.file_io.save_command_history(&self.command_history) {
+            log::warn!("saving command history failed: {}", err);
+        }
+    }
+
+    /// Replaces the command line with the previous history entry.
+    pub fn history_previous(&mut self) {
+        let index = match self.history_index {
+            _ if self.command_history.is_empty() => return,
+            None => {
+                self.history_draft = self.command_buffer.clone();
+                self.command_history.len() - 1
+            }
+            Some(i) => i.saturating_sub(1),
+        };
+        self.history_index = Some(index);
+        self.completions.clear();
+        self.command_buffer = self.command_history[index].clone();
+    }
+
+    /// Moves forward through history, ending on the text typed before
+    /// browsing started.
+    pub fn history_next(&mut self) {
+        let Some(index) = self.history_index else {
+            return;
+        };
+        self.completions.clear();
+        if index + 1 < self.command_history.len() {
+            self.history_index = Some(index + 1);
+            self.command_buffer = self.command_history[index + 1].clone();
+        } else {
+            self.history_index = None;
+            self.command_buffer = std::mem::take(&mut self.history_draft);
+        }
+    }
+
+    /// Forgets history and completion positions once the command line is
+    /// edited by hand.
+    pub fn reset_command_recall(&mut self) {
+        self.history_index = None;
+        self.completions.clear();
+    }
+
+    /// Completes the command name being typed. With several matches the
+    /// first is filled in, the candidates are listed in the status bar, and
+    /// repeated Tabs cycle through them.
+    pub fn complete_command(&mut self) {
+        let count_len = self
+            .command_buffer
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.command_buffer.len());
+        let (count, name) = self.command_buffer.split_at(count_len);
+        if name.contains(char::is_whitespace) {
+            return;
+        }
+
+        if self.completions.is_empty() {
+            self.completions = commands::completions(name);
+            self.completion_index = 0;
+            match self.completions.len() {
+                0 => {
+                    self.status_message = Some(format!("No command starts with '{}'", name));
+                    return;
+                }
+                1 => {}
+                _ => self.status_message = Some(self.completions.join("  ")),
+            }
+        } else {
+            self.completion_index = (self.completion_index + 1) % self.completions.len();
+        }
+
+        self.command_buffer = format!("{}{}", count, self.completions[self.completion_index]);
+        if self.completions.len() == 1 {
+            self.completions.clear();
+        }
+    }
+
     pub fn execute_command(&mut self) {
-        let cmd = self.command_buffer.trim().to_string();
+        let cmd = self.expand_alias(self.command_buffer.trim());
         self.command_buffer.clear();
 
         let (count, command) = Self::parse_command_count(&cmd);
+        // `:s/old/new/` has no space after its name, and its text may
+        // contain spaces of its own.
+        let (name, arg) = match command.split_once(char::is_whitespace) {
+            _ if command.starts_with("s/") => ("s".to_string(), command[1..].to_string()),
+            Some((name, arg)) => (name.to_string(), arg.trim().to_string()),
+            None => (command.clone(), String::new()),
+        };
+        // Arguments can carry note text, so they only appear at debug level
+        // and never once the data is encrypted.
+        log::info!("command: {} (count {})", name, count);
+        if !self.file_io.is_encrypting() {
+            log::debug!("command arguments: {:?}", arg);
+        }
 
-        match command.as_str() {
+        let before = STRUCTURAL_COMMANDS
+            .contains(&name.as_str())
+            .then(|| self.snapshot());
+
+        if let Err(err) = self.run_command(&name, &arg, count) {
+            log::info!("command rejected: {}", err);
+            self.status_message = Some(err.to_string());
+        }
+
+        if let Some(before) = before {
+            if before.notes != self.notes
+                || before.todos != self.todos
+                || before.catalogs != self.catalogs
+            {
+                self.structural_history.push(before);
+            }
+        }
+
+        // Commands that switch modes themselves (editing, renaming, help,
+        // main menu) keep that mode; everything else returns to Normal, or
+        // to the editor it was started from.
+        if matches!(self.mode, AppMode::Command) {
+            self.leave_command_mode();
+        }
+        self.mode_before_command = None;
+    }
+
+    /// Runs one parsed command line. A missing or malformed argument, or
+    /// an unknown name, comes back as a `CommandError` for the status bar.
+    fn run_command(&mut self, name: &str, arg: &str, count: usize) -> Result<(), CommandError> {
+        commands::check_arity(name, arg)?;
+        match name {
             "nn" => (0..count).for_each(|_| self.create_new_note()),
+            "nn-blank" => (0..count).for_each(|_| self.create_blank_note()),
             "ntodo" => (0..count).for_each(|_| self.create_new_todo()),
+            "nc" => self.create_catalog_item(arg)?,
+            "open" => self.open_catalog_url(),
             "del" => (0..count).for_each(|_| self.delete_current_item()),
+            "archive" => self.set_archived(true),
+            "done" => self.complete_todos(count, arg)?,
+            "unarchive" => self.set_archived(false),
+            "archived" => self.toggle_archived_view(),
+            "purge" => self.purge_current_item(),
             "rnm" => self.start_rename(),
             "mm" => self.go_to_main_menu(),
             "?" => self.show_help(),
-            "save" | "w" => self.save_data_with_status(),
+            "save" | "w" if arg.is_empty() => self.save_data_with_status(),
+            "save" | "w" => self.save_copy(arg),
             "backup" => self.backup_data_with_status(),
-            "export-md" | "export-markdown" => self.export_data_with_status("markdown"),
-            "export-csv" => self.export_data_with_status("csv"),
-            "q" | "quit" => self.handle_quit(),
-            "q!" => {}
-            _ => self.status_message = Some(format!("Unknown command: {}", command)),
+            "restore" => self.open_restore_picker(),
+            "import" => self.import_data(arg)?,
+            "import-md" => self.import_markdown(arg)?,
+            "export-md" | "export-markdown" => self.export_data_with_status("markdown", arg)?,
+            "export-csv" => self.export_data_with_status("csv", arg)?,
+            "export-one" => self.export_selected_item(arg)?,
+            "reflow" => self.reflow_current_note(arg)?,
+            "s" => self.substitute(arg)?,
+            "trim" => self.trim_current_note(),
+            "trim-all" => self.trim_all_notes(),
+            "dedup" if arg == "titles" => self.rename_duplicate_titles_with_status(),
+            "dedup" => self.find_duplicates(arg)?,
+            "zen" => self.enter_zen(),
+            "preview" => self.toggle_preview(),
+            "fix-dates" => self.fix_dates(),
+            "undo" => self.undo_structural(),
+            "sort" => self.sort_section(arg)?,
+            "filter" => self.set_todo_filter(arg)?,
+            "tagged" => self.set_tag_filter(arg, false),
+            "tagged-any" => self.set_tag_filter(arg, true),
+            "inspect" => self.inspect_selected_item(),
+            "goto" => self.open_goto(),
+            "find" => self.find(arg),
+            "tag" => self.update_tags(arg, true)?,
+            "untag" => self.update_tags(arg, false)?,
+            "due" => self.set_due_date(arg)?,
+            "cal" => self.open_date_picker(),
+            "recur" => self.set_recurrence(arg)?,
+            "theme" => self.set_theme(arg)?,
+            "set" => self.set_config_value(arg)?,
+            "wrap" => self.toggle_wrap(),
+            "goal" => self.set_word_goal(arg)?,
+            "pin" => self.set_pinned(true),
+            "unpin" => self.set_pinned(false),
+            "spawn-todo" => self.spawn_todo_from_note(),
+            "source" => self.jump_to_source_note(),
+            "follow" => self.follow_link(),
+            "encrypt" => self.start_choosing_passphrase(),
+            "decrypt" => self.disable_encryption(),
+            "alias" => self.define_alias(arg),
+            "date" => self.insert_timestamp(false),
+            "time" => self.insert_timestamp(true),
+            "q" | "quit" => self.request_quit(false),
+            "q!" => self.request_quit(true),
+            _ => return Err(CommandError::unknown(name)),
         }
+        Ok(())
+    }
 
-        if command != "?" {
-            self.mode = AppMode::Normal;
+    /// Ctrl+O while editing: run one command, then keep editing.
+    pub fn enter_command_from_editing(&mut self) {
+        self.mode_before_command = Some(AppMode::Editing);
+        self.mode = AppMode::Command;
+    }
+
+    /// Leaves command mode for the editor it was entered from, as long as
+    /// that item is still open, or for Normal mode.
+    pub fn leave_command_mode(&mut self) {
+        let editing = matches!(self.mode_before_command.take(), Some(AppMode::Editing));
+        self.mode = if editing && self.current_content().is_some() {
+            AppMode::Editing
+        } else {
+            AppMode::Normal
+        };
+    }
+
+    /// Replaces a user alias at the start of `cmd` (after any count
+    /// prefix) with its command, keeping the arguments. Expansion happens
+    /// once, so an alias naming another alias can't loop, and built-in
+    /// names always win.
+    fn expand_alias(&self, cmd: &str) -> String {
+        let rest = cmd.trim_start_matches(|c: char| c.is_ascii_digit());
+        let prefix = &cmd[..cmd.len() - rest.len()];
+        let (name, args) = match rest.split_once(char::is_whitespace) {
+            Some((name, args)) => (name, args),
+            None => (rest, ""),
+        };
+        match self.config.aliases.get(name) {
+            Some(command) if !commands::is_builtin(name) => {
+                format!("{}{} {}", prefix, command.trim(), args).trim().to_string()
+            }
+            _ => cmd.to_string(),
+        }
+    }
+
+    /// `:alias` lists aliases, `:alias <name>` shows one, and
+    /// `:alias <name> <command>` defines (or replaces) one and saves it.
+    fn define_alias(&mut self, arg: &str) {
+        let (name, command) = match arg.split_once(char::is_whitespace) {
+            Some((name, command)) => (name, command.trim()),
+            None => (arg, ""),
+        };
+        if name.is_empty() {
+            let mut aliases: Vec<String> = self
+                .config
+                .aliases
+                .iter()
+                .map(|(name, command)| format!("{} = {}", name, command))
+                .collect();
+            aliases.sort();
+            self.status_message = Some(if aliases.is_empty() {
+                "No aliases (alias <name> <command> adds one)".to_string()
+            } else {
+                aliases.join(", ")
+            });
+            return;
+        }
+        if command.is_empty() {
+            self.status_message = Some(match self.config.aliases.get(name) {
+                Some(command) => format!("{} = {}", name, command),
+                None => format!("No alias '{}'", name),
+            });
+            return;
+        }
+        if commands::is_builtin(name) || name.starts_with(|c: char| c.is_ascii_digit()) {
+            self.status_message = Some(format!("Cannot use '{}' as an alias name", name));
+            return;
         }
+
+        self.config.aliases.insert(name.to_string(), command.to_string());
+        self.status_message = Some(match self.file_io.save_config(&self.config) {
+            Ok(()) => format!("{} = {}", name, command),
+            Err(err) => format!("{} = {} (config not saved: {})", name, command, err),
+        });
     }
 
     fn parse_command_count(cmd: &str) -> (usize, String) {
@@ -175,18 +884,33 @@ impl App {
     }
 
     pub fn create_new_note(&mut self) {
+        self.create_blank_note();
+        self.current_note = self.selected_note;
+        self.cursor_pos = 0;
+        self.text_history.clear();
+        self.mode = AppMode::Editing;
+    }
+
+    /// Creates a note and selects it without opening it for editing.
+    pub fn create_blank_note(&mut self) {
         let now = Local::now();
         let new_note =
             Note::new(format!("Note {}", self.notes.len() + 1), now.to_rfc3339());
-        self.notes.push(new_note);
+        self.push_note(new_note);
         self.selected_note = Some(self.notes.len() - 1);
-        self.current_note = self.selected_note;
         self.section = AppSection::Notes;
-        self.mode = AppMode::Editing;
         self.status_message = Some("New note created".to_string());
         self.unsaved_changes = true;
     }
 
+    /// Appends `note` under a fresh id and returns the id.
+    fn push_note(&mut self, mut note: Note) -> u64 {
+        let id = next_note_id(&self.notes, &self.todos);
+        note.id = id;
+        self.notes.push(note);
+        id
+    }
+
     pub fn create_new_todo(&mut self) {
         let now = Local::now();
         let new_todo =
@@ -194,236 +918,2992 @@ impl App {
         self.todos.push(new_todo);
         self.selected_todo = Some(self.todos.len() - 1);
         self.current_todo = self.selected_todo;
+        self.cursor_pos = 0;
+        self.text_history.clear();
         self.section = AppSection::Todos;
         self.mode = AppMode::Editing;
         self.status_message = Some("New todo created".to_string());
         self.unsaved_changes = true;
     }
 
-    pub fn insert_char(&mut self, c: char) {
-        match self.section {
-            AppSection::Notes => {
-                if let Some(note) = self.current_note.and_then(|i| self.notes.get_mut(i)) {
-                    note.content.push(c);
-                    self.unsaved_changes = true;
-                }
-            }
-            AppSection::Todos => {
-                if let Some(todo) = self.current_todo.and_then(|i| self.todos.get_mut(i)) {
-                    todo.content.push(c);
-                    self.unsaved_changes = true;
-                }
-            }
-            _ => {}
+    /// Adds a catalog entry for `arg` (`<url> [name]`) and opens its
+    /// description for editing.
+    fn create_catalog_item(&mut self, arg: &str) -> Result<(), CommandError> {
+        let (url, name) = match arg.split_once(char::is_whitespace) {
+            Some((url, name)) => (url, name.trim()),
+            None => (arg, ""),
+        };
+        if url.is_empty() {
+            return Err(CommandError::invalid("nc", "nc needs a URL"));
         }
+        let name = if name.is_empty() { url } else { name };
+
+        self.catalogs
+            .push(CatalogItem::new(name.to_string(), String::new(), url.to_string()));
+        self.selected_catalog = Some(self.catalogs.len() - 1);
+        self.current_catalog = self.selected_catalog;
+        self.cursor_pos = 0;
+        self.text_history.clear();
+        self.section = AppSection::Catalog;
+        self.mode = AppMode::Editing;
+        self.status_message = Some("New catalog entry created".to_string());
+        self.unsaved_changes = true;
+        Ok(())
     }
 
-    pub fn delete_char(&mut self) {
-        match self.section {
-            AppSection::Notes => {
-                if let Some(note) = self.current_note.and_then(|i| self.notes.get_mut(i)) {
-                    note.content.pop();
-                    self.unsaved_changes = true;
-                }
-            }
-            AppSection::Todos => {
-                if let Some(todo) = self.current_todo.and_then(|i| self.todos.get_mut(i)) {
-                    todo.content.pop();
-                    self.unsaved_changes = true;
-                }
-            }
-            _ => {}
+    /// Launches the selected catalog entry's URL in the system browser.
+    fn open_catalog_url(&mut self) {
+        if !matches!(self.section, AppSection::Catalog) {
+            self.status_message = Some("Open works on catalog entries".to_string());
+            return;
         }
+        let Some(item) = self.selected_catalog.and_then(|i| self.catalogs.get(i)) else {
+            self.status_message = Some("No catalog entry selected".to_string());
+            return;
+        };
+
+        self.status_message = Some(match browser::open(&item.url) {
+            Ok(()) => format!("Opened {}", item.url),
+            Err(err) => {
+                log::warn!("opening {} failed: {}", item.url, err);
+                format!("Cannot open URL: {}", err)
+            }
+        });
     }
 
-    pub fn insert_new_line(&mut self) {
+    /// Opens the selected note or todo in the editor with the cursor at
+    /// the end of its content.
+    pub fn open_selected_item(&mut self) {
+        self.text_history.clear();
+        self.editor_hscroll.set(0);
         match self.section {
             AppSection::Notes => {
-                if let Some(note) = self.current_note.and_then(|i| self.notes.get_mut(i)) {
-                    note.content.push('\n');
-                    self.unsaved_changes = true;
+                if let Some(idx) = self.selected_note {
+                    self.current_note = Some(idx);
+                    self.cursor_pos = self.notes.get(idx).map_or(0, |n| n.content.len());
+                    self.mode = AppMode::Editing;
                 }
             }
             AppSection::Todos => {
-                if let Some(todo) = self.current_todo.and_then(|i| self.todos.get_mut(i)) {
-                    todo.content.push('\n');
-                    self.unsaved_changes = true;
+                if let Some(idx) = self.selected_todo {
+                    self.current_todo = Some(idx);
+                    self.cursor_pos = self.todos.get(idx).map_or(0, |t| t.content.len());
+                    self.mode = AppMode::Editing;
+                }
+            }
+            AppSection::Catalog => {
+                if let Some(idx) = self.selected_catalog {
+                    self.current_catalog = Some(idx);
+                    self.cursor_pos = self.catalogs.get(idx).map_or(0, |c| c.description.len());
+                    self.mode = AppMode::Editing;
                 }
             }
             _ => {}
         }
     }
 
-    pub fn toggle_todo_completion(&mut self) {
-        if let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i)) {
-            todo.completed = !todo.completed;
-            self.unsaved_changes = true;
-            self.status_message = Some(if todo.completed {
-                "Todo marked as completed".to_string()
-            } else {
-                "Todo marked as incomplete".to_string()
-            });
+    /// Content of the note or todo (or catalog description) open in the
+    /// editor.
+    fn current_content(&self) -> Option<&str> {
+        match self.section {
+            AppSection::Notes => self
+                .current_note
+                .and_then(|i| self.notes.get(i))
+                .map(|n| n.content.as_str()),
+            AppSection::Todos => self
+                .current_todo
+                .and_then(|i| self.todos.get(i))
+                .map(|t| t.content.as_str()),
+            AppSection::Catalog => self
+                .current_catalog
+                .and_then(|i| self.catalogs.get(i))
+                .map(|c| c.description.as_str()),
+            _ => None,
         }
     }
 
-    pub fn save_data(&self) -> Result<(), io::Error> {
-        self.file_io.save_data(&self.notes, &self.todos)
+    pub fn cursor_left(&mut self) {
+        self.move_cursor_horizontally(editor::prev_boundary);
     }
 
-    pub fn backup_data(&self) -> Result<std::path::PathBuf, io::Error> {
-        self.file_io.backup_data()
+    pub fn cursor_right(&mut self) {
+        self.move_cursor_horizontally(editor::next_boundary);
     }
 
-    pub fn export_data(
-        &self,
-        format: &str,
-        path: &std::path::Path,
-    ) -> Result<(), io::Error> {
-        self.file_io
-            .export_data(format, path, &self.notes, &self.todos)
+    pub fn cursor_word_left(&mut self) {
+        self.move_cursor_horizontally(editor::prev_word);
     }
 
-    pub fn has_unsaved_changes(&self) -> bool {
-        self.unsaved_changes
+    pub fn cursor_word_right(&mut self) {
+        self.move_cursor_horizontally(editor::next_word);
     }
 
-    pub fn delete_current_item(&mut self) {
-        match self.section {
-            AppSection::Notes => self.delete_note(),
-            AppSection::Todos => self.delete_todo(),
-            _ => {}
+    fn move_cursor_horizontally(&mut self, step: fn(&str, usize) -> usize) {
+        if let Some(content) = self.current_content() {
+            self.cursor_pos = step(content, self.cursor_pos);
+            self.preferred_column = None;
+            self.text_history.break_run();
         }
     }
 
-    fn delete_note(&mut self) {
-        if let Some(idx) = self.selected_note {
-            if idx < self.notes.len() {
-                self.notes.remove(idx);
-                self.unsaved_changes = true;
-                if self.notes.is_empty() {
-                    self.selected_note = None;
-                    self.current_note = None;
-                } else if idx >= self.notes.len() {
-                    self.selected_note = Some(self.notes.len() - 1);
-                }
-                self.status_message = Some("Note deleted".to_string());
-            }
-        }
+    pub fn cursor_up(&mut self) {
+        self.move_cursor_vertically(editor::line_up);
     }
 
-    fn delete_todo(&mut self) {
-        if let Some(idx) = self.selected_todo {
-            if idx < self.todos.len() {
-                self.todos.remove(idx);
-                self.unsaved_changes = true;
-                if self.todos.is_empty() {
-                    self.selected_todo = None;
-                    self.current_todo = None;
-                } else if idx >= self.todos.len() {
-                    self.selected_todo = Some(self.todos.len() - 1);
-                }
-                self.status_message = Some("Todo deleted".to_string());
-            }
+    pub fn cursor_down(&mut self) {
+        self.move_cursor_vertically(editor::line_down);
+    }
+
+    fn move_cursor_vertically(&mut self, step: fn(&str, usize, usize) -> usize) {
+        if let Some(content) = self.current_content() {
+            let column = self
+                .preferred_column
+                .unwrap_or_else(|| editor::column(content, self.cursor_pos));
+            self.cursor_pos = step(content, self.cursor_pos, column);
+            self.preferred_column = Some(column);
+            self.text_history.break_run();
+        }
+    }
+
+    pub fn cursor_line_start(&mut self) {
+        if let Some(content) = self.current_content() {
+            self.cursor_pos = editor::line_start(content, self.cursor_pos);
+            self.preferred_column = None;
+            self.text_history.break_run();
+        }
+    }
+
+    pub fn cursor_line_end(&mut self) {
+        if let Some(content) = self.current_content() {
+            self.cursor_pos = editor::line_end(content, self.cursor_pos);
+            self.preferred_column = None;
+            self.text_history.break_run();
+        }
+    }
+
+    /// Saves the open item's content and cursor before an edit of `kind`.
+    /// Every change to the open item's text passes through here, so it
+    /// also refreshes the item's `updated_at`.
+    fn record_edit(&mut self, kind: EditKind) {
+        self.touch_current_item();
+        if let Some(content) = self.current_content() {
+            let before = TextState {
+                content: content.to_string(),
+                cursor: self.cursor_pos,
+            };
+            self.text_history.record(before, kind);
+        }
+    }
+
+    fn touch_current_item(&mut self) {
+        let now = Local::now().to_rfc3339();
+        match self.section {
+            AppSection::Notes => {
+                if let Some(note) = self.current_note.and_then(|i| self.notes.get_mut(i)) {
+                    note.updated_at = now;
+                }
+            }
+            AppSection::Todos => {
+                if let Some(todo) = self.current_todo.and_then(|i| self.todos.get_mut(i)) {
+                    todo.updated_at = now;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn undo_edit(&mut self) {
+        self.step_edit_history(true);
+    }
+
+    pub fn redo_edit(&mut self) {
+        self.step_edit_history(false);
+    }
+
+    fn step_edit_history(&mut self, undo: bool) {
+        let Some(content) = self.current_content() else {
+            return;
+        };
+        let current = TextState {
+            content: content.to_string(),
+            cursor: self.cursor_pos,
+        };
+        let restored = if undo {
+            self.text_history.undo(current)
+        } else {
+            self.text_history.redo(current)
+        };
+
+        match restored {
+            Some(state) => {
+                if let Some(content) = self.current_content_mut() {
+                    *content = state.content;
+                }
+                self.cursor_pos = state.cursor;
+                self.preferred_column = None;
+                self.unsaved_changes = true;
+            }
+            None => {
+                self.status_message = Some(if undo {
+                    "Nothing to undo".to_string()
+                } else {
+                    "Nothing to redo".to_string()
+                });
+            }
+        }
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        self.record_edit(EditKind::Insert);
+        self.preferred_column = None;
+        let auto_pairs = self.config.auto_pairs;
+        let cursor = self.cursor_pos;
+        let Some(content) = self.current_content_mut() else {
+            return;
+        };
+
+        let pos = editor::clamp_to_boundary(content, cursor);
+        if auto_pairs && editor::is_closing(c) && content[pos..].starts_with(c) {
+            // Type over the closing character that auto-pairing inserted.
+            self.cursor_pos = pos + c.len_utf8();
+            return;
+        }
+
+        content.insert(pos, c);
+        if auto_pairs {
+            if let Some(close) = editor::closing_pair(c) {
+                content.insert(pos + c.len_utf8(), close);
+            }
+        }
+        self.cursor_pos = pos + c.len_utf8();
+        self.unsaved_changes = true;
+    }
+
+    /// Inserts the clipboard's text at the cursor as a single undo step.
+    pub fn paste_clipboard(&mut self) {
+        let text = match clipboard::paste() {
+            Ok(text) if text.is_empty() => {
+                self.status_message = Some("Clipboard is empty".to_string());
+                return;
+            }
+            Ok(text) => text,
+            Err(err) => {
+                self.status_message = Some(format!("Clipboard unavailable: {}", err));
+                return;
+            }
+        };
+
+        if self.insert_at_cursor(&text) {
+            self.status_message =
+                Some(format!("Pasted {} line(s)", text.lines().count().max(1)));
+        }
+    }
+
+    /// Inserts the current local date (`:date`, Ctrl+D) or time (`:time`,
+    /// Ctrl+T) at the cursor, formatted by `date_format` / `time_format`.
+    pub fn insert_timestamp(&mut self, time: bool) {
+        let format = if time { &self.config.time_format } else { &self.config.date_format };
+        let mut text = String::new();
+        // chrono reports a bad format string as a fmt::Error, which
+        // `to_string` would turn into a panic.
+        if write!(text, "{}", Local::now().format(format)).is_err() {
+            self.status_message = Some(format!("Invalid format '{}'", format));
+            return;
+        }
+        if !self.insert_at_cursor(&text) {
+            self.status_message = Some("Open a note or todo to insert into".to_string());
+        }
+    }
+
+    /// Inserts `text` at the editing cursor as one undoable edit; false if
+    /// nothing is open.
+    fn insert_at_cursor(&mut self, text: &str) -> bool {
+        if self.current_content().is_none() {
+            return false;
+        }
+        self.record_edit(EditKind::Other);
+        self.preferred_column = None;
+        let cursor = self.cursor_pos;
+        let Some(content) = self.current_content_mut() else {
+            return false;
+        };
+        let pos = editor::clamp_to_boundary(content, cursor);
+        content.insert_str(pos, text);
+        self.cursor_pos = pos + text.len();
+        self.unsaved_changes = true;
+        true
+    }
+
+    /// Copies the open item's whole content to the clipboard.
+    pub fn copy_current_content(&mut self) {
+        let Some(content) = self.current_content() else {
+            return;
+        };
+        self.status_message = Some(match clipboard::copy(content) {
+            Ok(()) => "Copied content".to_string(),
+            Err(err) => format!("Clipboard unavailable: {}", err),
+        });
+    }
+
+    pub fn delete_char(&mut self) {
+        let can_delete = self
+            .current_content()
+            .is_some_and(|content| editor::clamp_to_boundary(content, self.cursor_pos) > 0);
+        if !can_delete {
+            return;
+        }
+        self.record_edit(EditKind::Delete);
+        self.preferred_column = None;
+        let cursor = self.cursor_pos;
+        let Some(content) = self.current_content_mut() else {
+            return;
+        };
+
+        let pos = editor::clamp_to_boundary(content, cursor);
+        let start = editor::prev_boundary(content, pos);
+        if start < pos {
+            content.replace_range(start..pos, "");
+            self.cursor_pos = start;
+            self.unsaved_changes = true;
+        }
+    }
+
+    /// Deletes the word before the cursor along with the whitespace between
+    /// it and the cursor, so repeated presses walk back word by word.
+    pub fn delete_word(&mut self) {
+        let Some(content) = self.current_content() else {
+            return;
+        };
+        let end = editor::clamp_to_boundary(content, self.cursor_pos);
+        let start = editor::word_start_before(content, end);
+        if start == end {
+            return;
+        }
+
+        self.record_edit(EditKind::Other);
+        if let Some(content) = self.current_content_mut() {
+            content.replace_range(start..end, "");
+        }
+        self.cursor_pos = start;
+        self.preferred_column = None;
+        self.unsaved_changes = true;
+    }
+
+    /// Removes the line under the cursor of the open item, together with
+    /// its newline; on the last line the preceding newline goes instead.
+    pub fn delete_current_line(&mut self) {
+        let Some(content) = self.current_content() else {
+            self.status_message = Some("Open an item to delete lines from it".to_string());
+            return;
+        };
+        let cursor = editor::clamp_to_boundary(content, self.cursor_pos);
+        let start = editor::line_start(content, cursor);
+        let end = editor::line_end(content, cursor);
+        let range = if end < content.len() {
+            start..end + 1
+        } else if start > 0 {
+            start - 1..end
+        } else {
+            start..end
+        };
+        if range.is_empty() {
+            return;
+        }
+
+        self.record_edit(EditKind::Other);
+        if let Some(content) = self.current_content_mut() {
+            content.replace_range(range.clone(), "");
+            self.cursor_pos = editor::line_start(content, range.start.min(content.len()));
+        }
+        self.preferred_column = None;
+        self.unsaved_changes = true;
+    }
+
+    /// `:s/old/new/[g]`: replaces the first (or, with `g`, every)
+    /// occurrence of `old` in the open item. Ctrl+Z in the editor undoes it.
+    fn substitute(&mut self, spec: &str) -> Result<(), CommandError> {
+        let Some((pattern, replacement, global)) = commands::parse_substitute(spec)
+            .filter(|(pattern, _, _)| !pattern.is_empty())
+        else {
+            return Err(CommandError::invalid("s", format!("Cannot parse substitution '{}'", spec)));
+        };
+        let Some(content) = self.current_content() else {
+            self.status_message = Some("Open a note or todo to replace text in it".to_string());
+            return Ok(());
+        };
+        let count = match content.matches(pattern.as_str()).count() {
+            n if global => n,
+            n => n.min(1),
+        };
+        if count == 0 {
+            self.status_message = Some(format!("Not found: {}", pattern));
+            return Ok(());
+        }
+
+        self.record_edit(EditKind::Other);
+        let cursor = self.cursor_pos;
+        if let Some(content) = self.current_content_mut() {
+            *content = content.replacen(pattern.as_str(), &replacement, count);
+            self.cursor_pos = editor::clamp_to_boundary(content, cursor.min(content.len()));
+        }
+        self.preferred_column = None;
+        self.unsaved_changes = true;
+        self.status_message = Some(format!("Replaced {} occurrence(s)", count));
+        Ok(())
+    }
+
+    pub fn insert_new_line(&mut self) {
+        self.record_edit(EditKind::Other);
+        self.preferred_column = None;
+        let cursor = self.cursor_pos;
+        let Some(content) = self.current_content_mut() else {
+            return;
+        };
+
+        let pos = editor::clamp_to_boundary(content, cursor);
+        content.insert(pos, '\n');
+        self.cursor_pos = pos + 1;
+        self.unsaved_changes = true;
+    }
+
+    pub fn insert_tab(&mut self) {
+        if self.config.use_tabs {
+            self.insert_char('\t');
+        } else {
+            (0..self.config.tab_width).for_each(|_| self.insert_char(' '));
+        }
+    }
+
+    /// Removes one level of indentation (a tab or up to `tab_width` spaces)
+    /// from the line being edited.
+    pub fn dedent_line(&mut self) {
+        let tab_width = self.config.tab_width;
+        let Some(content) = self.current_content() else {
+            return;
+        };
+
+        let pos = editor::clamp_to_boundary(content, self.cursor_pos);
+        let line_start = editor::line_start(content, pos);
+        let line = &content[line_start..];
+        let remove = if line.starts_with('\t') {
+            1
+        } else {
+            line.chars().take(tab_width).take_while(|&c| c == ' ').count()
+        };
+        if remove == 0 {
+            return;
+        }
+
+        self.record_edit(EditKind::Other);
+        if let Some(content) = self.current_content_mut() {
+            content.replace_range(line_start..line_start + remove, "");
+            self.cursor_pos = pos.saturating_sub(remove).max(line_start);
+        }
+        self.unsaved_changes = true;
+    }
+
+    /// Content of the note or todo open in the editor.
+    fn current_content_mut(&mut self) -> Option<&mut String> {
+        match self.section {
+            AppSection::Notes => self
+                .current_note
+                .and_then(|i| self.notes.get_mut(i))
+                .map(|n| &mut n.content),
+            AppSection::Todos => self
+                .current_todo
+                .and_then(|i| self.todos.get_mut(i))
+                .map(|t| &mut t.content),
+            AppSection::Catalog => self
+                .current_catalog
+                .and_then(|i| self.catalogs.get_mut(i))
+                .map(|c| &mut c.description),
+            _ => None,
+        }
+    }
+
+    pub fn toggle_todo_completion(&mut self) {
+        let mut next = None;
+        if let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i)) {
+            todo.completed = !todo.completed;
+            todo.completed_at = if todo.completed {
+                Some(Local::now().to_rfc3339())
+            } else {
+                None
+            };
+            self.unsaved_changes = true;
+            self.status_message = Some(if todo.completed {
+                "Todo marked as completed".to_string()
+            } else {
+                "Todo marked as incomplete".to_string()
+            });
+            // The recurrence moves to the fresh copy so the finished one
+            // doesn't spawn again if it is toggled back and forth.
+            if todo.completed {
+                next = todo.next_occurrence();
+                if next.is_some() {
+                    todo.recurrence = None;
+                }
+            }
+        }
+        if let Some(next) = next {
+            self.status_message = Some(format!(
+                "Todo completed; next one due {}",
+                next.due_date.as_deref().unwrap_or_default()
+            ));
+            self.todos.push(next);
+        }
+
+        if self.config.archive_delay() == Some(chrono::Duration::zero()) {
+            self.archive_completed_todos(chrono::Duration::zero());
+        }
+        // The todo may no longer match a done/pending filter.
+        self.reselect_visible_todo();
+    }
+
+    /// `[n]done` / `done [n]` marks `n` todos complete, starting at the
+    /// selection and following the list order. Recurring ones queue their
+    /// next instance as when toggled one at a time.
+    fn complete_todos(&mut self, count: usize, arg: &str) -> Result<(), CommandError> {
+        if !matches!(self.section, AppSection::Todos) {
+            self.status_message = Some("Only todos can be completed".to_string());
+            return Ok(());
+        }
+        let count = if arg.is_empty() {
+            count
+        } else {
+            match arg.parse::<usize>() {
+                Ok(n) => n,
+                Err(_) => {
+                    return Err(CommandError::invalid("done", format!("'{}' is not a count", arg)));
+                }
+            }
+        };
+
+        let order = self.todo_display_order();
+        let start = self
+            .selected_todo
+            .and_then(|i| order.iter().position(|&idx| idx == i))
+            .unwrap_or(0);
+        let now = Local::now().to_rfc3339();
+        let mut changed = 0;
+        let mut next_instances = Vec::new();
+        for &i in order.iter().skip(start).take(count) {
+            let todo = &mut self.todos[i];
+            if todo.completed {
+                continue;
+            }
+            todo.completed = true;
+            todo.completed_at = Some(now.clone());
+            if let Some(next) = todo.next_occurrence() {
+                todo.recurrence = None;
+                next_instances.push(next);
+            }
+            changed += 1;
+        }
+        self.todos.extend(next_instances);
+
+        if changed > 0 {
+            self.unsaved_changes = true;
+        }
+        self.status_message = Some(format!("Marked {} todo(s) complete", changed));
+        if self.config.archive_delay() == Some(chrono::Duration::zero()) {
+            self.archive_completed_todos(chrono::Duration::zero());
+        }
+        self.reselect_visible_todo();
+        Ok(())
+    }
+
+    /// Shows only items tagged `arg` in the lists; no argument clears it.
+    fn set_tag_filter(&mut self, arg: &str, any: bool) {
+        let Some(filter) = TagFilter::parse(arg, any) else {
+            self.tag_filter = None;
+            self.status_message = Some("Tag filter cleared".to_string());
+            return;
+        };
+
+        let description = filter.to_string();
+        self.tag_filter = Some(filter);
+        self.reselect_visible_note();
+        self.reselect_visible_todo();
+        let order = self.catalog_display_order();
+        Self::reselect_visible(&order, &mut self.selected_catalog, &mut self.current_catalog);
+        let shown = self.visible_count();
+        self.status_message =
+            Some(format!("Showing items tagged {} ({})", description, shown));
+    }
+
+    /// Number of items the active section lists.
+    fn visible_count(&self) -> usize {
+        match self.section {
+            AppSection::Todos => self.todo_display_order().len(),
+            AppSection::Catalog => self.catalog_display_order().len(),
+            _ => self.note_display_order().len(),
+        }
+    }
+
+    /// While `:find <query>` or `:tagged <tags>` is being typed, shows how
+    /// many items it would match. Called on every keystroke in command mode.
+    pub fn preview_command_matches(&mut self) {
+        let Some((name, arg)) = self.command_buffer.trim_start().split_once(' ') else {
+            return;
+        };
+        let arg = arg.trim();
+        let count = match name {
+            "find" if !arg.is_empty() => search::count_matches(arg, &self.notes, &self.todos),
+            "tagged" | "tagged-any" => {
+                let Some(filter) = TagFilter::parse(arg, name == "tagged-any") else {
+                    return;
+                };
+                // Count under the typed filter without committing to it.
+                let previous = self.tag_filter.replace(filter);
+                let count = self.visible_count();
+                self.tag_filter = previous;
+                count
+            }
+            _ => return,
+        };
+        self.status_message = Some(format!("{} match(es)", count));
+    }
+
+    /// Limits the todo list to done, pending or overdue items; `all`
+    /// shows everything again.
+    fn set_todo_filter(&mut self, arg: &str) -> Result<(), CommandError> {
+        let Some(filter) = TodoFilter::parse(arg) else {
+            let problem = format!("Unknown filter '{}' (all, done, pending, overdue)", arg);
+            return Err(CommandError::invalid("filter", problem));
+        };
+        self.todo_filter = filter;
+        self.reselect_visible_todo();
+        if self.selected_todo.is_none() {
+            self.selected_todo = self.todo_display_order().first().copied();
+        }
+        self.status_message = Some(if filter == TodoFilter::All {
+            "Todo filter cleared".to_string()
+        } else {
+            format!("Showing {} todos ({})", filter, self.todo_display_order().len())
+        });
+        Ok(())
+    }
+
+    /// Opens the severity popup for the selected todo, highlighting its
+    /// current level.
+    pub fn open_severity_picker(&mut self) {
+        if let Some(todo) = self.selected_todo.and_then(|i| self.todos.get(i)) {
+            self.severity_choice = Severity::ALL
+                .iter()
+                .position(|s| *s == todo.severity)
+                .unwrap_or(0);
+            self.mode = AppMode::SeverityPicker;
+        }
+    }
+
+    pub fn next_severity_choice(&mut self) {
+        self.severity_choice = (self.severity_choice + 1) % Severity::ALL.len();
+    }
+
+    pub fn previous_severity_choice(&mut self) {
+        self.severity_choice =
+            (self.severity_choice + Severity::ALL.len() - 1) % Severity::ALL.len();
+    }
+
+    pub fn apply_severity_choice(&mut self) {
+        let severity = Severity::ALL[self.severity_choice].clone();
+        if let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i)) {
+            if todo.severity != severity {
+                todo.set_severity(severity.clone());
+                self.unsaved_changes = true;
+            }
+            self.status_message = Some(format!("Severity set to {}", severity));
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    /// `+`/`-`: moves the selected todo's severity one level up or down,
+    /// stopping at Critical and Info.
+    pub fn step_severity(&mut self, raise: bool) {
+        let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i)) else {
+            return;
+        };
+        let severity = if raise { todo.severity.raised() } else { todo.severity.lowered() };
+        if todo.severity == severity {
+            self.status_message = Some(format!("Severity is already {}", severity));
+            return;
+        }
+        todo.set_severity(severity.clone());
+        self.unsaved_changes = true;
+        self.status_message = Some(format!("Severity set to {}", severity));
+    }
+
+    /// `:cal` opens a month calendar on the selected todo's due date (or
+    /// today) to pick a new one.
+    pub fn open_date_picker(&mut self) {
+        if !matches!(self.section, AppSection::Todos) {
+            self.status_message = Some("Due dates only apply to todos".to_string());
+            return;
+        }
+        let Some(todo) = self.selected_todo.and_then(|i| self.todos.get(i)) else {
+            self.status_message = Some("No todo selected".to_string());
+            return;
+        };
+        self.date_choice = todo
+            .due_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .unwrap_or_else(|| Local::now().date_naive());
+        self.mode = AppMode::DatePicker;
+    }
+
+    /// Moves the calendar selection by `days`, forwards or back.
+    pub fn move_date_choice(&mut self, days: i64) {
+        let step = Days::new(days.unsigned_abs());
+        let moved = if days < 0 {
+            self.date_choice.checked_sub_days(step)
+        } else {
+            self.date_choice.checked_add_days(step)
+        };
+        if let Some(date) = moved {
+            self.date_choice = date;
+        }
+    }
+
+    /// Moves the calendar selection a month, clamping to the month's end.
+    pub fn move_date_choice_month(&mut self, forward: bool) {
+        let moved = if forward {
+            self.date_choice.checked_add_months(Months::new(1))
+        } else {
+            self.date_choice.checked_sub_months(Months::new(1))
+        };
+        if let Some(date) = moved {
+            self.date_choice = date;
+        }
+    }
+
+    pub fn apply_date_choice(&mut self) {
+        self.mode = AppMode::Normal;
+        let date = self.date_choice.format("%Y-%m-%d").to_string();
+        if let Err(err) = self.set_due_date(&date) {
+            self.status_message = Some(err.to_string());
+        }
+    }
+
+    /// Adds or removes the comma-separated tags in `arg` on the selected
+    /// item, then reports the item's current tags.
+    fn update_tags(&mut self, arg: &str, add: bool) -> Result<(), CommandError> {
+        let command = if add { "tag" } else { "untag" };
+        let names: Vec<&str> = arg.split(',').map(str::trim).filter(|t| !t.is_empty()).collect();
+        if names.is_empty() {
+            return Err(CommandError::invalid(command, "No tag names given"));
+        }
+        // Check every name up front so one bad tag doesn't leave the rest
+        // half applied.
+        if add {
+            names
+                .iter()
+                .try_for_each(|name| normalize_tag(name).map(drop))
+                .map_err(|e| CommandError::invalid(command, e))?;
+        }
+
+        let now = Local::now().to_rfc3339();
+        let tags: Option<Result<(bool, Vec<String>), String>> = match self.section {
+            AppSection::Notes => self.selected_note.and_then(|i| self.notes.get_mut(i)).map(|n| {
+                let before = n.tags.clone();
+                if add {
+                    names.iter().try_for_each(|name| n.add_tag(name))?;
+                } else {
+                    names.iter().for_each(|name| n.remove_tag(name));
+                }
+                if n.tags != before {
+                    n.updated_at = now.clone();
+                }
+                Ok((n.tags != before, n.tags.clone()))
+            }),
+            AppSection::Todos => self.selected_todo.and_then(|i| self.todos.get_mut(i)).map(|t| {
+                let before = t.tags.clone();
+                if add {
+                    names.iter().try_for_each(|name| t.add_tag(name))?;
+                } else {
+                    names.iter().for_each(|name| t.remove_tag(name));
+                }
+                if t.tags != before {
+                    t.updated_at = now.clone();
+                }
+                Ok((t.tags != before, t.tags.clone()))
+            }),
+            AppSection::Catalog => {
+                self.selected_catalog.and_then(|i| self.catalogs.get_mut(i)).map(|c| {
+                    let before = c.tags.clone();
+                    if add {
+                        names.iter().try_for_each(|name| {
+                            c.add_tag(normalize_tag(name)?);
+                            Ok::<_, String>(())
+                        })?;
+                    } else {
+                        names.iter().for_each(|name| c.remove_tag(name));
+                    }
+                    Ok((c.tags != before, c.tags.clone()))
+                })
+            }
+            AppSection::Help => None,
+        };
+
+        let tags = tags.transpose().map_err(|e| CommandError::invalid(command, e))?;
+        let Some((changed, tags)) = tags else {
+            self.status_message = Some("No item selected".to_string());
+            return Ok(());
+        };
+        if changed {
+            self.unsaved_changes = true;
+        }
+        self.status_message = Some(if tags.is_empty() {
+            "No tags".to_string()
+        } else {
+            format!("Tags: {}", tags.join(", "))
+        });
+        Ok(())
+    }
+
+    /// Sets or clears (`:due clear`) the selected todo's due date.
+    fn set_due_date(&mut self, arg: &str) -> Result<(), CommandError> {
+        if arg.is_empty() {
+            return Err(CommandError::invalid("due", "No date given"));
+        }
+        if !matches!(self.section, AppSection::Todos) {
+            self.status_message = Some("Due dates only apply to todos".to_string());
+            return Ok(());
+        }
+        let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i)) else {
+            self.status_message = Some("No todo selected".to_string());
+            return Ok(());
+        };
+
+        if arg == "clear" {
+            todo.due_date = None;
+            self.status_message = Some("Due date cleared".to_string());
+        } else {
+            todo.set_due_date(arg).map_err(|e| {
+                CommandError::invalid("due", format!("Invalid date '{}': {}", arg, e))
+            })?;
+            self.status_message = Some(format!("Due {}", arg));
+        }
+        self.unsaved_changes = true;
+        Ok(())
+    }
+
+    /// `:wrap` toggles soft wrapping for the open (or selected) note.
+    fn toggle_wrap(&mut self) {
+        let index = if matches!(self.section, AppSection::Notes) {
+            self.current_note.or(self.selected_note)
+        } else {
+            None
+        };
+        let Some(note) = index.and_then(|i| self.notes.get_mut(i)) else {
+            self.status_message = Some("Wrapping applies to notes".to_string());
+            return;
+        };
+
+        note.wrap = !note.wrap;
+        self.editor_hscroll.set(0);
+        self.unsaved_changes = true;
+        self.status_message = Some(if note.wrap {
+            "Wrapping on".to_string()
+        } else {
+            "Wrapping off (h/l scroll sideways)".to_string()
+        });
+    }
+
+    /// `:goal <n>` sets a word count target for the open (or selected)
+    /// note; `:goal 0` removes it.
+    fn set_word_goal(&mut self, arg: &str) -> Result<(), CommandError> {
+        if arg.is_empty() {
+            return Err(CommandError::invalid("goal", "No word count given"));
+        }
+        let Ok(goal) = arg.parse::<usize>() else {
+            return Err(CommandError::invalid("goal", format!("'{}' is not a word count", arg)));
+        };
+        let index = if matches!(self.section, AppSection::Notes) {
+            self.active_note_index()
+        } else {
+            None
+        };
+        let Some(note) = index.and_then(|i| self.notes.get_mut(i)) else {
+            self.status_message = Some("Word goals apply to notes".to_string());
+            return Ok(());
+        };
+
+        note.word_goal = (goal > 0).then_some(goal);
+        self.unsaved_changes = true;
+        self.status_message = Some(match note.word_goal {
+            Some(goal) => format!("Word goal: {} ({} so far)", goal, note.word_count()),
+            None => "Word goal removed".to_string(),
+        });
+        Ok(())
+    }
+
+    /// Scrolls an unwrapped note sideways while it isn't being edited.
+    pub fn scroll_editor(&mut self, right: bool) {
+        let step = 8;
+        let offset = self.editor_hscroll.get();
+        self.editor_hscroll.set(if right {
+            offset + step
+        } else {
+            offset.saturating_sub(step)
+        });
+    }
+
+    fn set_pinned(&mut self, pinned: bool) {
+        if !matches!(self.section, AppSection::Notes) {
+            self.status_message = Some("Only notes can be pinned".to_string());
+            return;
+        }
+        let Some(note) = self.selected_note.and_then(|i| self.notes.get_mut(i)) else {
+            self.status_message = Some("No note selected".to_string());
+            return;
+        };
+
+        if note.pinned != pinned {
+            note.pinned = pinned;
+            self.unsaved_changes = true;
+        }
+        let message = if pinned { "Note pinned" } else { "Note unpinned" };
+        self.status_message = Some(message.to_string());
+    }
+
+    fn set_recurrence(&mut self, arg: &str) -> Result<(), CommandError> {
+        if !matches!(self.section, AppSection::Todos) {
+            self.status_message = Some("Recurrence only applies to todos".to_string());
+            return Ok(());
+        }
+        let recurrence = match arg {
+            "none" => None,
+            _ => match Recurrence::parse(arg) {
+                Some(recurrence) => Some(recurrence),
+                None => {
+                    let periods = "daily, weekly, monthly, none";
+                    let problem = format!("Unknown period '{}' ({})", arg, periods);
+                    return Err(CommandError::invalid("recur", problem));
+                }
+            },
+        };
+        let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i)) else {
+            self.status_message = Some("No todo selected".to_string());
+            return Ok(());
+        };
+
+        todo.recurrence = recurrence;
+        self.unsaved_changes = true;
+        self.status_message = Some(match recurrence {
+            Some(recurrence) => format!("Todo repeats {}", recurrence),
+            None => "Todo no longer repeats".to_string(),
+        });
+        Ok(())
+    }
+
+    /// Archives completed todos that have been done for at least `delay`,
+    /// moving the selection off any todo that disappears from the list.
+    fn archive_completed_todos(&mut self, delay: chrono::Duration) {
+        let mut archived = 0;
+        for todo in self.todos.iter_mut() {
+            if !todo.archived && todo.completed_for_at_least(delay) {
+                todo.archived = true;
+                archived += 1;
+            }
+        }
+
+        if archived > 0 {
+            self.unsaved_changes = true;
+            self.reselect_visible_todo();
+            self.status_message = Some(format!("Archived {} completed todo(s)", archived));
+        }
+    }
+
+    fn reselect_visible_todo(&mut self) {
+        let order = self.todo_display_order();
+        Self::reselect_visible(&order, &mut self.selected_todo, &mut self.current_todo);
+    }
+
+    fn reselect_visible_note(&mut self) {
+        let order = self.note_display_order();
+        Self::reselect_visible(&order, &mut self.selected_note, &mut self.current_note);
+    }
+
+    /// Moves a selection that is no longer in `order` to the next visible
+    /// item (or the last one), and closes a hidden open item.
+    fn reselect_visible(
+        order: &[usize],
+        selected: &mut Option<usize>,
+        current: &mut Option<usize>,
+    ) {
+        if current.is_some_and(|i| !order.contains(&i)) {
+            *current = None;
+        }
+        if let Some(idx) = *selected {
+            if !order.contains(&idx) {
+                *selected = order
+                    .iter()
+                    .copied()
+                    .find(|&i| i > idx)
+                    .or_else(|| order.last().copied());
+            }
+        }
+    }
+
+    fn selected_title(&self) -> Option<String> {
+        match self.section {
+            AppSection::Notes => self
+                .selected_note
+                .and_then(|i| self.notes.get(i))
+                .map(|n| n.title.clone()),
+            AppSection::Todos => self
+                .selected_todo
+                .and_then(|i| self.todos.get(i))
+                .map(|t| t.title.clone()),
+            AppSection::Catalog => self
+                .selected_catalog
+                .and_then(|i| self.catalogs.get(i))
+                .map(|c| c.name.clone()),
+            _ => None,
+        }
+    }
+
+    /// Location summary such as "Locus · Todos · Editing · 'Ship release'",
+    /// used for the status bar title and the terminal window title.
+    pub fn breadcrumb(&self) -> String {
+        let mut parts = vec!["Locus".to_string()];
+        if matches!(self.mode, AppMode::MainMenu) {
+            parts.push("Menu".to_string());
+            return parts.join(" · ");
+        }
+
+        parts.push(
+            match self.section {
+                AppSection::Notes => "Notes",
+                AppSection::Todos => "Todos",
+                AppSection::Catalog => "Catalog",
+                AppSection::Help => "Help",
+            }
+            .to_string(),
+        );
+        parts.push(
+            match self.mode {
+                AppMode::MainMenu => "Menu",
+                AppMode::Normal => "Normal",
+                AppMode::Command => "Command",
+                AppMode::Editing => "Editing",
+                AppMode::Help => "Help",
+                AppMode::Renaming => "Rename",
+                AppMode::Confirm => "Confirm",
+                AppMode::SeverityPicker => "Severity",
+                AppMode::Inspect => "Inspect",
+                AppMode::Search => "Search",
+                AppMode::Restore => "Restore",
+                AppMode::Goto => "Go to",
+                AppMode::Password => "Passphrase",
+                AppMode::QuitPrompt => "Quit",
+                AppMode::DatePicker => "Due date",
+            }
+            .to_string(),
+        );
+
+        let title = if matches!(self.mode, AppMode::Editing) {
+            match self.section {
+                AppSection::Notes => self
+                    .current_note
+                    .and_then(|i| self.notes.get(i))
+                    .map(|n| n.title.clone()),
+                AppSection::Todos => self
+                    .current_todo
+                    .and_then(|i| self.todos.get(i))
+                    .map(|t| t.title.clone()),
+                AppSection::Catalog => self
+                    .current_catalog
+                    .and_then(|i| self.catalogs.get(i))
+                    .map(|c| c.name.clone()),
+                _ => None,
+            }
+        } else {
+            self.selected_title()
+        };
+        if let Some(title) = title {
+            parts.push(format!("'{}'", title));
+        }
+        parts.join(" · ")
+    }
+
+    pub fn copy_selected_title(&mut self) {
+        let Some(title) = self.selected_title() else {
+            self.status_message = Some("Nothing selected to copy".to_string());
+            return;
+        };
+
+        self.status_message = Some(match clipboard::copy(&title) {
+            Ok(()) => "Copied title".to_string(),
+            Err(err) => format!("Clipboard unavailable: {}", err),
+        });
+    }
+
+    pub fn save_data(&self) -> Result<(), io::Error> {
+        if self.locked {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "data is still locked",
+            ));
+        }
+        self.file_io.save_data(
+            &self.notes,
+            &self.todos,
+            &self.catalogs,
+            self.config.normalize_on_save,
+        )
+    }
+
+    /// Starts asking for a new passphrase to encrypt `data.json` with.
+    fn start_choosing_passphrase(&mut self) {
+        self.password_buffer.clear();
+        self.password_step = PasswordStep::Choose;
+        self.mode = AppMode::Password;
+        self.status_message = Some("Choose a passphrase to encrypt data.json".to_string());
+    }
+
+    pub fn submit_password(&mut self) {
+        let passphrase = std::mem::take(&mut self.password_buffer);
+        match std::mem::replace(&mut self.password_step, PasswordStep::Unlock) {
+            PasswordStep::Unlock => self.unlock(passphrase),
+            PasswordStep::Choose if passphrase.is_empty() => {
+                self.password_step = PasswordStep::Choose;
+                self.status_message = Some("The passphrase can't be empty".to_string());
+            }
+            PasswordStep::Choose => {
+                self.password_step = PasswordStep::Confirm(passphrase);
+                self.status_message = Some("Type the passphrase again".to_string());
+            }
+            PasswordStep::Confirm(first) if first == passphrase => {
+                self.enable_encryption(passphrase)
+            }
+            PasswordStep::Confirm(_) => {
+                self.password_step = PasswordStep::Choose;
+                self.status_message =
+                    Some("Passphrases didn't match; choose one again".to_string());
+            }
+        }
+    }
+
+    /// Esc at the unlock prompt quits, since there is nothing to work on;
+    /// while choosing a passphrase it leaves the data unencrypted.
+    pub fn cancel_password(&mut self) {
+        self.password_buffer.clear();
+        if matches!(self.password_step, PasswordStep::Unlock) {
+            self.should_quit = true;
+        } else {
+            self.mode = AppMode::Normal;
+            self.status_message = Some("Encryption not enabled".to_string());
+        }
+    }
+
+    fn unlock(&mut self, passphrase: String) {
+        self.file_io.set_passphrase(Some(passphrase));
+        match self.file_io.load_data() {
+            Ok(data) => {
+                self.notes = data.notes;
+                self.todos = data.todos;
+                self.catalogs = data.catalogs;
+                self.locked = false;
+                // Older versions kept writing it in plaintext.
+                if let Err(err) = self.file_io.remove_command_history() {
+                    log::warn!("removing command history failed: {}", err);
+                }
+                self.rename_duplicate_titles_on_load();
+                self.apply_saved_sorts();
+                self.mode = AppMode::MainMenu;
+                self.restore_session();
+                self.status_message = Some("Data unlocked".to_string());
+            }
+            Err(err) => {
+                log::warn!("unlocking data failed: {}", err);
+                self.file_io.set_passphrase(None);
+                self.status_message = Some(format!("Could not unlock data.json: {}", err));
+            }
+        }
+    }
+
+    fn enable_encryption(&mut self, passphrase: String) {
+        self.file_io.set_passphrase(Some(passphrase));
+        self.config.encrypt = true;
+        self.mode = AppMode::Normal;
+        let result = self
+            .save_data()
+            .and_then(|_| self.file_io.save_config(&self.config))
+            .and_then(|_| self.file_io.remove_command_history());
+        self.status_message = Some(match result {
+            Ok(()) => {
+                self.unsaved_changes = false;
+                "Data encrypted".to_string()
+            }
+            Err(err) => {
+                log::error!("saving encrypted data failed: {}", err);
+                format!("Encrypting failed: {}", err)
+            }
+        });
+    }
+
+    fn disable_encryption(&mut self) {
+        if !self.file_io.is_encrypting() {
+            self.status_message = Some("Data isn't encrypted".to_string());
+            return;
+        }
+        self.file_io.set_passphrase(None);
+        self.config.encrypt = false;
+        let result = self
+            .save_data()
+            .and_then(|_| self.file_io.save_config(&self.config));
+        self.status_message = Some(match result {
+            Ok(()) => {
+                self.unsaved_changes = false;
+                "Data saved without encryption".to_string()
+            }
+            Err(err) => format!("Decrypting failed: {}", err),
+        });
+    }
+
+    pub fn backup_data(&self) -> Result<(PathBuf, Vec<PathBuf>), io::Error> {
+        self.file_io.backup_data(self.config.max_backups)
+    }
+
+    /// Exports the notes and todos the lists currently show, or with
+    /// `all` every note and todo, returning how many of each were written.
+    pub fn export_data(
+        &self,
+        format: &str,
+        path: &std::path::Path,
+        all: bool,
+    ) -> Result<(usize, usize), io::Error> {
+        let (notes, todos): (Vec<Note>, Vec<Todo>) = if all {
+            (self.notes.clone(), self.todos.clone())
+        } else {
+            (
+                self.note_display_order().iter().map(|&i| self.notes[i].clone()).collect(),
+                self.todo_display_order().iter().map(|&i| self.todos[i].clone()).collect(),
+            )
+        };
+        self.file_io.export_data(format, path, &notes, &todos)?;
+        Ok((notes.len(), todos.len()))
+    }
+
+    /// The list filters in effect, e.g. "tag: work, filter: overdue".
+    fn active_filters(&self) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(tags) = &self.tag_filter {
+            filters.push(format!("tag: {}", tags));
+        }
+        if self.todo_filter != TodoFilter::All {
+            filters.push(format!("filter: {}", self.todo_filter));
+        }
+        if self.show_archived {
+            filters.push("archived".to_string());
+        }
+        filters
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.unsaved_changes
+    }
+
+    /// Notes and todos are archived rather than removed; see `:purge`.
+    pub fn delete_current_item(&mut self) {
+        match self.section {
+            AppSection::Notes | AppSection::Todos => self.set_archived(true),
+            AppSection::Catalog => self.delete_catalog_item(),
+            _ => {}
+        }
+    }
+
+    /// Archives (`archived == true`) or restores the selected note or
+    /// todo; it moves between the live list and the `:archived` view.
+    fn set_archived(&mut self, archived: bool) {
+        let (kind, flag) = match self.section {
+            AppSection::Notes => (
+                "Note",
+                self.selected_note.and_then(|i| self.notes.get_mut(i)).map(|n| &mut n.archived),
+            ),
+            AppSection::Todos => (
+                "Todo",
+                self.selected_todo.and_then(|i| self.todos.get_mut(i)).map(|t| &mut t.archived),
+            ),
+            _ => {
+                self.status_message = Some("Only notes and todos can be archived".to_string());
+                return;
+            }
+        };
+        let Some(flag) = flag else {
+            self.status_message = Some(format!("No {} selected", kind.to_lowercase()));
+            return;
+        };
+        if *flag == archived {
+            self.status_message = Some(if archived {
+                format!("{} is already archived; :purge deletes it for good", kind)
+            } else {
+                format!("{} isn't archived", kind)
+            });
+            return;
+        }
+
+        *flag = archived;
+        self.unsaved_changes = true;
+        self.reselect_visible_note();
+        self.reselect_visible_todo();
+        self.status_message = Some(if archived {
+            format!("{} archived (:archived to browse)", kind)
+        } else {
+            format!("{} restored", kind)
+        });
+    }
+
+    /// `:archived` switches between the live lists and the archive.
+    fn toggle_archived_view(&mut self) {
+        self.show_archived = !self.show_archived;
+        self.reselect_visible_note();
+        self.reselect_visible_todo();
+        if self.selected_note.is_none() {
+            self.selected_note = self.note_display_order().first().copied();
+        }
+        if self.selected_todo.is_none() {
+            self.selected_todo = self.todo_display_order().first().copied();
+        }
+        self.status_message = Some(if self.show_archived {
+            "Showing archived items (:unarchive restores, :purge deletes)".to_string()
+        } else {
+            "Showing live items".to_string()
+        });
+    }
+
+    /// Permanently removes the selected item, archived or not.
+    fn purge_current_item(&mut self) {
+        match self.section {
+            AppSection::Notes => self.purge_note(),
+            AppSection::Todos => self.purge_todo(),
+            AppSection::Catalog => self.delete_catalog_item(),
+            _ => {}
+        }
+    }
+
+    fn purge_note(&mut self) {
+        if let Some(idx) = self.selected_note {
+            if idx < self.notes.len() {
+                self.notes.remove(idx);
+                Self::follow_removal(&mut self.current_note, idx);
+                self.unsaved_changes = true;
+                if self.notes.is_empty() {
+                    self.selected_note = None;
+                    self.current_note = None;
+                } else if idx >= self.notes.len() {
+                    self.selected_note = Some(self.notes.len() - 1);
+                }
+                self.reselect_visible_note();
+                self.status_message = Some("Note deleted permanently".to_string());
+            }
+        }
+    }
+
+    fn purge_todo(&mut self) {
+        if let Some(idx) = self.selected_todo {
+            if idx < self.todos.len() {
+                self.todos.remove(idx);
+                Self::follow_removal(&mut self.current_todo, idx);
+                self.unsaved_changes = true;
+                if self.todos.is_empty() {
+                    self.selected_todo = None;
+                    self.current_todo = None;
+                } else if idx >= self.todos.len() {
+                    self.selected_todo = Some(self.todos.len() - 1);
+                }
+                self.reselect_visible_todo();
+                self.status_message = Some("Todo deleted permanently".to_string());
+            }
+        }
+    }
+
+    fn delete_catalog_item(&mut self) {
+        if let Some(idx) = self.selected_catalog {
+            if idx < self.catalogs.len() {
+                self.catalogs.remove(idx);
+                Self::follow_removal(&mut self.current_catalog, idx);
+                self.unsaved_changes = true;
+                if self.catalogs.is_empty() {
+                    self.selected_catalog = None;
+                    self.current_catalog = None;
+                } else if idx >= self.catalogs.len() {
+                    self.selected_catalog = Some(self.catalogs.len() - 1);
+                }
+                self.status_message = Some("Catalog entry deleted".to_string());
+            }
         }
     }
 
     pub fn start_rename(&mut self) {
         self.command_buffer.clear();
-        let title = match self.section {
+        let title = self.selected_title();
+
+        if let Some(title) = title {
+            self.command_buffer = title;
+            self.mode = AppMode::Renaming;
+            self.status_message = Some("Enter new name:".to_string());
+        }
+    }
+
+    pub fn finish_rename(&mut self, new_name: String) {
+        match self.section {
+            AppSection::Notes => {
+                if let Some(note) = self.selected_note.and_then(|i| self.notes.get_mut(i))
+                {
+                    note.title = new_name;
+                    note.updated_at = Local::now().to_rfc3339();
+                    self.unsaved_changes = true;
+                    self.status_message = Some("Note renamed".to_string());
+                }
+            }
+            AppSection::Todos => {
+                if let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i))
+                {
+                    todo.title = new_name;
+                    todo.updated_at = Local::now().to_rfc3339();
+                    self.unsaved_changes = true;
+                    self.status_message = Some("Todo renamed".to_string());
+                }
+            }
+            AppSection::Catalog => {
+                if let Some(item) =
+                    self.selected_catalog.and_then(|i| self.catalogs.get_mut(i))
+                {
+                    item.name = new_name;
+                    self.unsaved_changes = true;
+                    self.status_message = Some("Catalog entry renamed".to_string());
+                }
+            }
+            _ => {}
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    fn go_to_main_menu(&mut self) {
+        self.mode = AppMode::MainMenu;
+        self.section = AppSection::Notes;
+        self.status_message = Some("Main Menu".to_string());
+        self.current_note = None;
+        self.current_todo = None;
+        self.current_catalog = None;
+    }
+
+    fn show_help(&mut self) {
+        self.mode = AppMode::Help;
+        self.status_message = Some("Help".to_string());
+    }
+
+    fn save_data_with_status(&mut self) {
+        match self.save_data() {
+            Ok(_) => {
+                log::info!("saved {} notes and {} todos", self.notes.len(), self.todos.len());
+                self.status_message = Some("Data saved successfully".to_string());
+                self.unsaved_changes = false;
+            }
+            Err(err) => {
+                log::error!("save failed: {}", err);
+                self.status_message = Some(format!("Error saving data: {}", err));
+            }
+        }
+    }
+
+    /// `:w <path>` writes the current data to another file, leaving
+    /// `data.json` and the unsaved-changes flag alone.
+    fn save_copy(&mut self, arg: &str) {
+        let path = expand_home(arg);
+        let result = if self.locked {
+            Err(io::Error::new(io::ErrorKind::PermissionDenied, "data is still locked"))
+        } else {
+            self.file_io.save_data_to(
+                &path,
+                &self.notes,
+                &self.todos,
+                &self.catalogs,
+                self.config.normalize_on_save,
+            )
+        };
+        match result {
+            Ok(()) => {
+                log::info!("saved a copy to {:?}", path);
+                self.status_message = Some(format!("Saved a copy to {}", path.display()));
+            }
+            Err(err) => {
+                log::error!("saving a copy to {:?} failed: {}", path, err);
+                self.status_message =
+                    Some(format!("Cannot save to {}: {}", path.display(), err));
+            }
+        }
+    }
+
+    fn open_restore_picker(&mut self) {
+        match self.file_io.list_backups() {
+            Ok(backups) if backups.is_empty() => {
+                self.status_message = Some("No backups found".to_string());
+            }
+            Ok(backups) => {
+                self.backups = backups;
+                self.selected_backup = 0;
+                self.mode = AppMode::Restore;
+            }
+            Err(err) => {
+                self.status_message = Some(format!("Error listing backups: {}", err));
+            }
+        }
+    }
+
+    pub fn next_backup(&mut self) {
+        if !self.backups.is_empty() {
+            self.selected_backup = (self.selected_backup + 1) % self.backups.len();
+        }
+    }
+
+    pub fn previous_backup(&mut self) {
+        if !self.backups.is_empty() {
+            let len = self.backups.len();
+            self.selected_backup = (self.selected_backup + len - 1) % len;
+        }
+    }
+
+    /// Restores the highlighted backup over `data.json` and reloads it.
+    /// The in-memory state is saved first so the safety backup taken by
+    /// `FileIO::restore_backup` includes any unsaved changes.
+    pub fn restore_selected_backup(&mut self) {
+        let Some(path) = self.backups.get(self.selected_backup).cloned() else {
+            return;
+        };
+        self.mode = AppMode::Normal;
+
+        let result = self
+            .save_data()
+            .and_then(|_| self.file_io.restore_backup(&path, self.config.max_backups));
+        match result {
+            Ok(data) => {
+                log::info!("restored {:?}", path);
+                self.status_message = Some(format!(
+                    "Restored {}: {} notes, {} todos",
+                    path.file_name().unwrap_or_default().to_string_lossy(),
+                    data.notes.len(),
+                    data.todos.len()
+                ));
+                self.notes = data.notes;
+                self.todos = data.todos;
+                self.catalogs = data.catalogs;
+                self.selected_note = None;
+                self.selected_todo = None;
+                self.selected_catalog = None;
+                self.current_note = None;
+                self.current_todo = None;
+                self.current_catalog = None;
+                self.text_history.clear();
+                self.unsaved_changes = false;
+                self.apply_saved_sorts();
+            }
+            Err(err) => {
+                log::error!("restore from {:?} failed: {}", path, err);
+                self.status_message = Some(format!("Restore failed: {}", err));
+            }
+        }
+    }
+
+    fn backup_data_with_status(&mut self) {
+        match self.backup_data() {
+            Ok((path, removed)) => {
+                log::info!("backup created at {:?}", path);
+                for old in &removed {
+                    log::info!("removed old backup {:?}", old);
+                }
+                self.status_message = Some(if removed.is_empty() {
+                    format!("Backup created at: {:?}", path)
+                } else {
+                    format!(
+                        "Backup created at: {:?} (removed {} old backup(s))",
+                        path,
+                        removed.len()
+                    )
+                });
+            }
+            Err(err) => {
+                log::error!("backup failed: {}", err);
+                self.status_message = Some(format!("Error creating backup: {}", err));
+            }
+        }
+    }
+
+    /// `:export-md` / `:export-csv` write what the lists show under the
+    /// active filters; `--all` exports everything regardless.
+    fn export_data_with_status(&mut self, format: &str, arg: &str) -> Result<(), CommandError> {
+        let all = match arg {
+            "" => false,
+            "--all" => true,
+            _ => {
+                let command = if format == "markdown" { "export-md" } else { "export-csv" };
+                return Err(CommandError::invalid(command, format!("Unknown option '{}'", arg)));
+            }
+        };
+        let path = default_export_path(format);
+        match self.export_data(format, &path, all) {
+            Ok((notes, todos)) => {
+                log::info!("exported {} notes, {} todos as {} to {:?}", notes, todos, format, path);
+                let filters = self.active_filters();
+                let scope = if all || filters.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", filters.join(", "))
+                };
+                self.status_message = Some(format!(
+                    "Exported {} notes and {} todos{} to: {:?}",
+                    notes, todos, scope, path
+                ));
+            }
+            Err(err) => {
+                log::error!("export to {} failed: {}", format, err);
+                self.status_message =
+                    Some(format!("Error exporting to {}: {}", format, err))
+            }
+        }
+        Ok(())
+    }
+
+    /// Exports just the open or selected item to the home directory.
+    fn export_selected_item(&mut self, format: &str) -> Result<(), CommandError> {
+        if !matches!(format, "markdown" | "md" | "csv" | "txt") {
+            let problem = format!("Unknown format '{}' (markdown, csv, txt)", format);
+            return Err(CommandError::invalid("export-one", problem));
+        }
+        let dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+        let result = match self.section {
+            AppSection::Notes => self
+                .active_note_index()
+                .and_then(|i| self.notes.get(i))
+                .map(|note| self.file_io.export_note(note, format, &dir)),
+            AppSection::Todos => self
+                .current_todo
+                .or(self.selected_todo)
+                .and_then(|i| self.todos.get(i))
+                .map(|todo| self.file_io.export_todo(todo, format, &dir)),
+            AppSection::Catalog | AppSection::Help => None,
+        };
+
+        self.status_message = Some(match result {
+            None => "No item selected".to_string(),
+            Some(Ok(path)) => {
+                log::info!("exported item as {} to {:?}", format, path);
+                format!("Exported to: {:?}", path)
+            }
+            Some(Err(err)) => {
+                log::error!("single-item export failed: {}", err);
+                format!("Error exporting item: {}", err)
+            }
+        });
+        Ok(())
+    }
+
+    /// Appends the notes and todos from another data file, renaming any
+    /// whose title is already taken.
+    fn import_data(&mut self, arg: &str) -> Result<(), CommandError> {
+        if arg.is_empty() {
+            return Err(CommandError::invalid("import", "No file given"));
+        }
+        let path = expand_home(arg);
+
+        let (notes, todos) = match self.file_io.import_data(&path) {
+            Ok(data) => (data.notes, data.todos),
+            Err(err) => {
+                log::error!("import from {:?} failed: {}", path, err);
+                self.status_message = Some(format!("Cannot import {}: {}", path.display(), err));
+                return Ok(());
+            }
+        };
+
+        let (note_count, todo_count) = (notes.len(), todos.len());
+        // Imported ids may clash with ours, so notes get fresh ones and the
+        // imported todos' links follow them.
+        let mut new_ids = HashMap::new();
+        for mut note in notes {
+            note.title = unique_title(self.notes.iter().map(|n| n.title.as_str()), &note.title);
+            let old_id = note.id;
+            new_ids.insert(old_id, self.push_note(note));
+        }
+        for mut todo in todos {
+            todo.title = unique_title(self.todos.iter().map(|t| t.title.as_str()), &todo.title);
+            todo.source_note_id = todo.source_note_id.and_then(|id| new_ids.get(&id).copied());
+            self.todos.push(todo);
+        }
+
+        if note_count + todo_count > 0 {
+            self.unsaved_changes = true;
+        }
+        log::info!("imported {} notes and {} todos from {:?}", note_count, todo_count, path);
+        self.status_message = Some(format!(
+            "Imported {} notes and {} todos",
+            note_count, todo_count
+        ));
+        Ok(())
+    }
+
+    /// Creates one note per `#`/`##` section of a Markdown file.
+    fn import_markdown(&mut self, arg: &str) -> Result<(), CommandError> {
+        if arg.is_empty() {
+            return Err(CommandError::invalid("import-md", "No file given"));
+        }
+        let path = expand_home(arg);
+
+        let sections = match self.file_io.import_markdown(&path) {
+            Ok(sections) => sections,
+            Err(err) => {
+                log::error!("markdown import from {:?} failed: {}", path, err);
+                self.status_message = Some(format!("Cannot import {}: {}", path.display(), err));
+                return Ok(());
+            }
+        };
+
+        let count = sections.len();
+        let now = Local::now().to_rfc3339();
+        for (title, content) in sections {
+            let title = unique_title(self.notes.iter().map(|n| n.title.as_str()), &title);
+            let mut note = Note::new(title, now.clone());
+            note.content = content;
+            self.push_note(note);
+        }
+
+        if count > 0 {
+            self.unsaved_changes = true;
+        }
+        log::info!("imported {} notes from markdown {:?}", count, path);
+        self.status_message = Some(format!("Imported {} notes", count));
+        Ok(())
+    }
+
+    /// `--add-note`: appends a note holding `content`, titled `title` (made
+    /// unique) or `Note N`, and returns the title it ended up with.
+    pub fn add_note(&mut self, title: Option<&str>, content: String) -> String {
+        let default_title = format!("Note {}", self.notes.len() + 1);
+        let title = title.map(str::trim).filter(|t| !t.is_empty()).unwrap_or(&default_title);
+        let title = unique_title(self.notes.iter().map(|n| n.title.as_str()), title);
+        let mut note = Note::new(title.clone(), Local::now().to_rfc3339());
+        note.content = content;
+        self.push_note(note);
+        self.unsaved_changes = true;
+        log::info!("added note {:?} from the command line", title);
+        title
+    }
+
+    fn active_note_index(&self) -> Option<usize> {
+        self.current_note.or(self.selected_note)
+    }
+
+    fn reflow_current_note(&mut self, arg: &str) -> Result<(), CommandError> {
+        let width = if arg.is_empty() {
+            80
+        } else {
+            match arg.parse::<usize>() {
+                Ok(width) => width,
+                Err(_) => {
+                    return Err(CommandError::invalid("reflow", format!("Invalid width '{}'", arg)));
+                }
+            }
+        };
+
+        if !matches!(self.section, AppSection::Notes) {
+            self.status_message = Some("Reflow only works on notes".to_string());
+            return Ok(());
+        }
+
+        if let Some(note) = self.active_note_index().and_then(|i| self.notes.get_mut(i)) {
+            if note.reflow(width) {
+                self.unsaved_changes = true;
+                self.status_message = Some(format!("Note reflowed to width {}", width));
+            } else {
+                self.status_message = Some("Nothing to reflow".to_string());
+            }
+        } else {
+            self.status_message = Some("No note selected".to_string());
+        }
+        Ok(())
+    }
+
+    /// Opens the active note full screen with all other chrome hidden.
+    fn enter_zen(&mut self) {
+        if !matches!(self.section, AppSection::Notes) {
+            self.status_message = Some("Zen mode only works on notes".to_string());
+            return;
+        }
+        match self.active_note_index() {
+            Some(idx) => {
+                self.selected_note = Some(idx);
+                self.open_selected_item();
+                self.zen = true;
+            }
+            None => self.status_message = Some("No note selected".to_string()),
+        }
+    }
+
+    /// Switches the note pane between raw text and rendered Markdown.
+    fn toggle_preview(&mut self) {
+        if !matches!(self.section, AppSection::Notes) {
+            self.status_message = Some("Preview only works on notes".to_string());
+            return;
+        }
+        let Some(idx) = self.active_note_index() else {
+            self.status_message = Some("No note selected".to_string());
+            return;
+        };
+        self.current_note = Some(idx);
+        self.preview = !self.preview;
+        self.status_message = Some(
+            if self.preview { "Preview on" } else { "Preview off" }.to_string(),
+        );
+    }
+
+    /// Leaves editing, dropping back to the regular layout if zen mode was on.
+    pub fn stop_editing(&mut self) {
+        self.zen = false;
+        self.mode = AppMode::Normal;
+    }
+
+    fn trim_current_note(&mut self) {
+        if !matches!(self.section, AppSection::Notes) {
+            self.status_message = Some("Trim only works on notes".to_string());
+            return;
+        }
+
+        if let Some(note) = self.active_note_index().and_then(|i| self.notes.get_mut(i)) {
+            if note.trim_whitespace() {
+                self.unsaved_changes = true;
+                self.status_message = Some("Note whitespace trimmed".to_string());
+            } else {
+                self.status_message = Some("Nothing to trim".to_string());
+            }
+        } else {
+            self.status_message = Some("No note selected".to_string());
+        }
+    }
+
+    fn trim_all_notes(&mut self) {
+        let trimmed = self
+            .notes
+            .iter_mut()
+            .map(|note| note.trim_whitespace())
+            .filter(|&changed| changed)
+            .count();
+        if trimmed > 0 {
+            self.unsaved_changes = true;
+        }
+        self.status_message = Some(format!("Trimmed whitespace in {} note(s)", trimmed));
+    }
+
+    /// The `rename_duplicate_titles` startup pass.
+    fn rename_duplicate_titles_on_load(&mut self) {
+        if !self.config.rename_duplicate_titles {
+            return;
+        }
+        let renamed = self.rename_duplicate_titles();
+        if renamed > 0 {
+            log::info!("renamed {} duplicate titles on load", renamed);
+            self.status_message = Some(format!("Renamed {} duplicate title(s)", renamed));
+        }
+    }
+
+    /// `:dedup titles`: the same renaming on demand.
+    fn rename_duplicate_titles_with_status(&mut self) {
+        let renamed = self.rename_duplicate_titles();
+        self.status_message = Some(if renamed == 0 {
+            "No duplicate titles".to_string()
+        } else {
+            format!("Renamed {} duplicate title(s)", renamed)
+        });
+    }
+
+    /// Gives every note and todo whose title repeats an earlier one in its
+    /// list a numbered suffix. Returns how many were renamed.
+    fn rename_duplicate_titles(&mut self) -> usize {
+        let now = Local::now().to_rfc3339();
+        let mut renamed = 0;
+        let titles: Vec<String> = self.notes.iter().map(|n| n.title.clone()).collect();
+        for (note, title) in self.notes.iter_mut().zip(disambiguate_titles(&titles)) {
+            if note.title != title {
+                note.title = title;
+                note.updated_at = now.clone();
+                renamed += 1;
+            }
+        }
+        let titles: Vec<String> = self.todos.iter().map(|t| t.title.clone()).collect();
+        for (todo, title) in self.todos.iter_mut().zip(disambiguate_titles(&titles)) {
+            if todo.title != title {
+                todo.title = title;
+                todo.updated_at = now.clone();
+                renamed += 1;
+            }
+        }
+        if renamed > 0 {
+            self.unsaved_changes = true;
+        }
+        renamed
+    }
+
+    /// Finds items in the active section whose normalized title and content
+    /// match an older item, and asks for confirmation before removing them.
+    fn find_duplicates(&mut self, arg: &str) -> Result<(), CommandError> {
+        if !arg.is_empty() {
+            return Err(CommandError::invalid("dedup", format!("Unknown option '{}'", arg)));
+        }
+        // Only the items the list shows are candidates, so a live item is
+        // never dropped in favour of an older archived copy.
+        let (kind, order, positions) = match self.section {
+            AppSection::Notes => {
+                let order = self.note_display_order();
+                let positions = duplicate_indices(order.iter().map(|&i| {
+                    let n = &self.notes[i];
+                    (dedup_key(&n.title, &n.content), n.created_at.as_str())
+                }));
+                ("note", order, positions)
+            }
+            AppSection::Todos => {
+                let order = self.todo_display_order();
+                let positions = duplicate_indices(order.iter().map(|&i| {
+                    let t = &self.todos[i];
+                    (dedup_key(&t.title, &t.content), t.created_at.as_str())
+                }));
+                ("todo", order, positions)
+            }
+            _ => {
+                self.status_message = Some("Dedup works on notes or todos".to_string());
+                return Ok(());
+            }
+        };
+        let mut duplicates: Vec<usize> = positions.into_iter().map(|p| order[p]).collect();
+        duplicates.sort_unstable();
+
+        if duplicates.is_empty() {
+            self.status_message = Some(format!("No duplicate {}s found", kind));
+            return Ok(());
+        }
+
+        let titles: Vec<String> = duplicates
+            .iter()
+            .map(|&i| match self.section {
+                AppSection::Todos => format!("'{}'", self.todos[i].title),
+                _ => format!("'{}'", self.notes[i].title),
+            })
+            .collect();
+        self.status_message = Some(format!(
+            "Archive {} duplicate {}(s): {}? (y/n)",
+            duplicates.len(),
+            kind,
+            titles.join(", ")
+        ));
+        self.pending_action = Some(match self.section {
+            AppSection::Todos => PendingAction::ArchiveDuplicateTodos(duplicates),
+            _ => PendingAction::ArchiveDuplicateNotes(duplicates),
+        });
+        self.mode = AppMode::Confirm;
+        Ok(())
+    }
+
+    pub fn confirm_pending_action(&mut self) {
+        if self.pending_action.is_some() {
+            let snapshot = self.snapshot();
+            self.structural_history.push(snapshot);
+        }
+        match self.pending_action.take() {
+            Some(PendingAction::ArchiveDuplicateNotes(indices)) => {
+                for &i in &indices {
+                    self.notes[i].archived = true;
+                }
+                self.reselect_visible_note();
+                if self.selected_note.is_none() {
+                    self.selected_note = self.note_display_order().first().copied();
+                }
+                self.unsaved_changes = true;
+                self.status_message = Some(format!(
+                    "Archived {} duplicate note(s) (:archived to browse)",
+                    indices.len()
+                ));
+            }
+            Some(PendingAction::ArchiveDuplicateTodos(indices)) => {
+                for &i in &indices {
+                    self.todos[i].archived = true;
+                }
+                self.reselect_visible_todo();
+                if self.selected_todo.is_none() {
+                    self.selected_todo = self.todo_display_order().first().copied();
+                }
+                self.unsaved_changes = true;
+                self.status_message = Some(format!(
+                    "Archived {} duplicate todo(s) (:archived to browse)",
+                    indices.len()
+                ));
+            }
+            None => {}
+        }
+        self.mode = AppMode::Normal;
+    }
+
+    pub fn cancel_pending_action(&mut self) {
+        self.pending_action = None;
+        self.mode = AppMode::Normal;
+        self.status_message = Some("Canceled".to_string());
+    }
+
+    /// Searches every note and todo for `query` and lists the matches.
+    /// An empty query clears the previous results.
+    fn find(&mut self, query: &str) {
+        self.search_query = query.to_string();
+        self.selected_result = 0;
+        if query.is_empty() {
+            self.search_results.clear();
+            self.status_message = Some("Search cleared".to_string());
+            return;
+        }
+
+        self.search_results = search::search(query, &self.notes, &self.todos);
+        self.status_message = Some(format!(
+            "{} match(es) for '{}'",
+            self.search_results.len(),
+            query
+        ));
+        self.mode = AppMode::Search;
+    }
+
+    pub fn next_search_result(&mut self) {
+        if !self.search_results.is_empty() {
+            self.selected_result = (self.selected_result + 1) % self.search_results.len();
+        }
+    }
+
+    pub fn previous_search_result(&mut self) {
+        if !self.search_results.is_empty() {
+            let len = self.search_results.len();
+            self.selected_result = (self.selected_result + len - 1) % len;
+        }
+    }
+
+    /// Jumps to the highlighted search result and opens it for editing.
+    pub fn open_search_result(&mut self) {
+        let Some(result) = self.search_results.get(self.selected_result) else {
+            return;
+        };
+        self.open_item_ref(result.item);
+    }
+
+    /// Opens the go-to picker with every note and todo listed.
+    pub fn open_goto(&mut self) {
+        self.goto_query.clear();
+        self.refresh_goto_matches();
+        self.mode = AppMode::Goto;
+    }
+
+    pub fn goto_push(&mut self, c: char) {
+        self.goto_query.push(c);
+        self.refresh_goto_matches();
+    }
+
+    pub fn goto_pop(&mut self) {
+        self.goto_query.pop();
+        self.refresh_goto_matches();
+    }
+
+    fn refresh_goto_matches(&mut self) {
+        let notes = self.notes.iter().enumerate();
+        let todos = self.todos.iter().enumerate();
+        let items: Vec<(ItemRef, &str)> = notes
+            .map(|(i, n)| (ItemRef::Note(i), n.title.as_str()))
+            .chain(todos.map(|(i, t)| (ItemRef::Todo(i), t.title.as_str())))
+            .collect();
+        let titles: Vec<&str> = items.iter().map(|&(_, title)| title).collect();
+        self.goto_matches = search::fuzzy_rank(&self.goto_query, &titles)
+            .into_iter()
+            .map(|i| items[i].0)
+            .collect();
+        self.selected_goto = 0;
+    }
+
+    pub fn next_goto_match(&mut self) {
+        if !self.goto_matches.is_empty() {
+            self.selected_goto = (self.selected_goto + 1) % self.goto_matches.len();
+        }
+    }
+
+    pub fn previous_goto_match(&mut self) {
+        if !self.goto_matches.is_empty() {
+            let len = self.goto_matches.len();
+            self.selected_goto = (self.selected_goto + len - 1) % len;
+        }
+    }
+
+    pub fn open_goto_match(&mut self) {
+        match self.goto_matches.get(self.selected_goto) {
+            Some(&item) => self.open_item_ref(item),
+            None => self.mode = AppMode::Normal,
+        }
+    }
+
+    /// Switches to the item's section, selects it and opens it.
+    fn open_item_ref(&mut self, item: ItemRef) {
+        match item {
+            ItemRef::Note(i) if i < self.notes.len() => {
+                self.section = AppSection::Notes;
+                self.selected_note = Some(i);
+            }
+            ItemRef::Todo(i) if i < self.todos.len() => {
+                self.section = AppSection::Todos;
+                self.selected_todo = Some(i);
+            }
+            _ => {
+                self.status_message = Some("Result no longer exists".to_string());
+                self.mode = AppMode::Normal;
+                return;
+            }
+        }
+        self.open_selected_item();
+    }
+
+    /// Opens the note named by a `[[title]]` link in the open (or selected)
+    /// note or todo: the link under the editing cursor, else the first one.
+    pub fn follow_link(&mut self) {
+        let (content, cursor) = match self.section {
+            AppSection::Notes => {
+                let open = self.current_note.is_some();
+                match self.current_note.or(self.selected_note).and_then(|i| self.notes.get(i)) {
+                    Some(note) => (&note.content, if open { self.cursor_pos } else { 0 }),
+                    None => return,
+                }
+            }
+            AppSection::Todos => {
+                let open = self.current_todo.is_some();
+                match self.current_todo.or(self.selected_todo).and_then(|i| self.todos.get(i)) {
+                    Some(todo) => (&todo.content, if open { self.cursor_pos } else { 0 }),
+                    None => return,
+                }
+            }
+            _ => return,
+        };
+        let Some(target) = links::link_near(content, cursor) else {
+            self.status_message = Some("No [[links]] here".to_string());
+            return;
+        };
+        match links::resolve(&target, &self.notes) {
+            Some(idx) => {
+                self.open_item_ref(ItemRef::Note(idx));
+                self.status_message = Some(format!("Followed link to '{}'", target));
+            }
+            None => self.status_message = Some(format!("No note titled '{}'", target)),
+        }
+    }
+
+    /// Shows the selected item exactly as it is serialized to `data.json`.
+    fn inspect_selected_item(&mut self) {
+        let json = match self.section {
             AppSection::Notes => self
                 .selected_note
                 .and_then(|i| self.notes.get(i))
-                .map(|n| n.title.clone()),
+                .map(serde_json::to_string_pretty),
             AppSection::Todos => self
                 .selected_todo
                 .and_then(|i| self.todos.get(i))
-                .map(|t| t.title.clone()),
+                .map(serde_json::to_string_pretty),
+            AppSection::Catalog => self
+                .selected_catalog
+                .and_then(|i| self.catalogs.get(i))
+                .map(serde_json::to_string_pretty),
             _ => None,
         };
 
-        if let Some(title) = title {
-            self.command_buffer = title;
-            self.mode = AppMode::Renaming;
-            self.status_message = Some("Enter new name:".to_string());
+        match json {
+            Some(Ok(json)) => {
+                self.inspect_text = json;
+                self.inspect_scroll = 0;
+                self.mode = AppMode::Inspect;
+            }
+            Some(Err(err)) => self.status_message = Some(format!("Cannot inspect: {}", err)),
+            None => self.status_message = Some("Nothing selected to inspect".to_string()),
         }
     }
 
-    pub fn finish_rename(&mut self, new_name: String) {
-        match self.section {
-            AppSection::Notes => {
-                if let Some(note) = self.selected_note.and_then(|i| self.notes.get_mut(i))
-                {
-                    note.title = new_name;
-                    self.unsaved_changes = true;
-                    self.status_message = Some("Note renamed".to_string());
-                }
+    pub fn scroll_inspect(&mut self, down: bool) {
+        let max = self.inspect_text.lines().count().saturating_sub(1) as u16;
+        self.inspect_scroll = if down {
+            (self.inspect_scroll + 1).min(max)
+        } else {
+            self.inspect_scroll.saturating_sub(1)
+        };
+    }
+
+    /// `:set <key> <value>` changes a `config.json` setting and saves it;
+    /// `:set <key>` shows the current value.
+    fn set_config_value(&mut self, arg: &str) -> Result<(), CommandError> {
+        let (key, value) = match arg.split_once(' ') {
+            Some((key, value)) => (key, value.trim()),
+            None => (arg, ""),
+        };
+        if key.is_empty() {
+            return Err(CommandError::invalid("set", "No setting given"));
+        }
+        if value.is_empty() {
+            let current = self.config.get(key).ok_or_else(|| {
+                CommandError::invalid("set", format!("Unknown setting '{}'", key))
+            })?;
+            self.status_message = Some(format!("{} = {}", key, current));
+            return Ok(());
+        }
+
+        match self.config.with_value(key, value) {
+            Ok(config) => {
+                self.config = config;
+                self.theme = Theme::from_config(&self.config);
+                self.keymap = Keymap::new(&self.config.keymap);
+                self.list_width =
+                    self.config.list_width_percent.clamp(LIST_WIDTH_MIN, LIST_WIDTH_MAX);
+                let current = self.config.get(key).unwrap_or_default();
+                self.status_message = Some(match self.file_io.save_config(&self.config) {
+                    Ok(()) => format!("{} = {}", key, current),
+                    Err(err) => format!("{} = {} (config not saved: {})", key, current, err),
+                });
             }
-            AppSection::Todos => {
-                if let Some(todo) = self.selected_todo.and_then(|i| self.todos.get_mut(i))
-                {
-                    todo.title = new_name;
-                    self.unsaved_changes = true;
-                    self.status_message = Some("Todo renamed".to_string());
-                }
+            Err(msg) => return Err(CommandError::invalid("set", msg)),
+        }
+        Ok(())
+    }
+
+    /// Ctrl+L / Ctrl+H: moves the divider between the list and the editor
+    /// one step right or left and remembers it in the config.
+    pub fn resize_list(&mut self, wider: bool) {
+        let width = if wider {
+            (self.list_width + LIST_WIDTH_STEP).min(LIST_WIDTH_MAX)
+        } else {
+            self.list_width.saturating_sub(LIST_WIDTH_STEP).max(LIST_WIDTH_MIN)
+        };
+        if width == self.list_width {
+            let limit = if wider { "widest" } else { "narrowest" };
+            self.status_message = Some(format!("List pane is at its {} ({}%)", limit, width));
+            return;
+        }
+
+        self.list_width = width;
+        self.config.list_width_percent = width;
+        self.status_message = Some(match self.file_io.save_config(&self.config) {
+            Ok(()) => format!("List width: {}%", width),
+            Err(err) => format!("List width: {}% (config not saved: {})", width, err),
+        });
+    }
+
+    /// Switches to a built-in color scheme and remembers it in the config.
+    fn set_theme(&mut self, arg: &str) -> Result<(), CommandError> {
+        if !THEME_NAMES.contains(&arg) {
+            let problem = format!("Unknown theme '{}' ({})", arg, THEME_NAMES.join(", "));
+            return Err(CommandError::invalid("theme", problem));
+        }
+
+        self.config.theme = arg.to_string();
+        self.theme = Theme::from_config(&self.config);
+        self.status_message = Some(match self.file_io.save_config(&self.config) {
+            Ok(()) => format!("Theme: {}", arg),
+            Err(err) => format!("Theme: {} (config not saved: {})", arg, err),
+        });
+        Ok(())
+    }
+
+    /// Sorts the active section by `arg` and remembers the key in the config
+    /// so the same order is restored on the next launch. Repeating the
+    /// current key flips between ascending and descending.
+    fn sort_section(&mut self, arg: &str) -> Result<(), CommandError> {
+        let Some(key) = SortKey::parse(arg) else {
+            let keys = "title, created, modified, due, severity, none";
+            return Err(CommandError::invalid("sort", format!("Unknown key '{}' ({})", arg, keys)));
+        };
+
+        let (current, descending) = match self.section {
+            AppSection::Notes if key == SortKey::Due => {
+                self.status_message = Some("Only todos have due dates".to_string());
+                return Ok(());
             }
-            _ => {}
+            AppSection::Notes if key == SortKey::Severity => {
+                self.status_message = Some("Only todos have severities".to_string());
+                return Ok(());
+            }
+            AppSection::Notes => (self.config.notes_sort, self.config.notes_sort_descending),
+            AppSection::Todos => (self.config.todos_sort, self.config.todos_sort_descending),
+            _ => {
+                self.status_message = Some("Nothing to sort here".to_string());
+                return Ok(());
+            }
+        };
+        let descending = key != SortKey::None && key == current && !descending;
+
+        if matches!(self.section, AppSection::Notes) {
+            self.sort_notes(key, descending);
+            self.config.notes_sort = key;
+            self.config.notes_sort_descending = descending;
+        } else {
+            self.sort_todos(key, descending);
+            self.config.todos_sort = key;
+            self.config.todos_sort_descending = descending;
         }
-        self.mode = AppMode::Normal;
+
+        if key != SortKey::None {
+            self.unsaved_changes = true;
+        }
+        let direction = if descending { " (descending)" } else { "" };
+        self.status_message = Some(match self.file_io.save_config(&self.config) {
+            Ok(()) => format!("Sorted by {}{}", key, direction),
+            Err(err) => format!("Sorted by {}{} (config not saved: {})", key, direction, err),
+        });
+        Ok(())
     }
 
-    fn go_to_main_menu(&mut self) {
-        self.mode = AppMode::MainMenu;
-        self.section = AppSection::Notes;
-        self.status_message = Some("Main Menu".to_string());
-        self.current_note = None;
+    /// Re-applies the sorts remembered in the config, e.g. after loading.
+    fn apply_saved_sorts(&mut self) {
+        self.sort_notes(self.config.notes_sort, self.config.notes_sort_descending);
+        self.sort_todos(self.config.todos_sort, self.config.todos_sort_descending);
+    }
+
+    // Selections are remapped through the permutation, so the same items
+    // stay selected and open after sorting.
+    fn sort_notes(&mut self, key: SortKey, descending: bool) {
+        let order = sort::sorted_order(&self.notes, key, descending);
+        sort::reorder(&mut self.notes, &order);
+        self.selected_note = sort::new_position(&order, self.selected_note);
+        self.current_note = sort::new_position(&order, self.current_note);
+    }
+
+    fn sort_todos(&mut self, key: SortKey, descending: bool) {
+        let order = sort::sorted_order(&self.todos, key, descending);
+        sort::reorder(&mut self.todos, &order);
+        self.selected_todo = sort::new_position(&order, self.selected_todo);
+        self.current_todo = sort::new_position(&order, self.current_todo);
+    }
+
+    /// Creates a todo linked back to the active note and selects it.
+    fn spawn_todo_from_note(&mut self) {
+        if !matches!(self.section, AppSection::Notes) {
+            self.status_message = Some("Spawn a todo from the notes section".to_string());
+            return;
+        }
+        let Some(note) = self.active_note_index().and_then(|i| self.notes.get(i)) else {
+            self.status_message = Some("No note selected".to_string());
+            return;
+        };
+
+        let mut todo = Todo::new(note.title.clone(), Local::now().to_rfc3339());
+        todo.content = format!("From note: {}", note.title);
+        todo.source_note_id = Some(note.id);
+        let title = note.title.clone();
+
+        self.todos.push(todo);
+        self.selected_todo = Some(self.todos.len() - 1);
         self.current_todo = None;
+        self.section = AppSection::Todos;
+        self.unsaved_changes = true;
+        self.status_message = Some(format!("Todo created from note '{}'", title));
     }
 
-    fn show_help(&mut self) {
-        self.mode = AppMode::Help;
-        self.status_message = Some("Help".to_string());
+    /// Opens the note the selected todo was spawned from.
+    fn jump_to_source_note(&mut self) {
+        let source = self
+            .selected_todo
+            .and_then(|i| self.todos.get(i))
+            .filter(|_| matches!(self.section, AppSection::Todos))
+            .and_then(|t| t.source_note_id);
+        let Some(source) = source else {
+            self.status_message = Some("Selected todo has no source note".to_string());
+            return;
+        };
+
+        match self.notes.iter().position(|n| n.id == source) {
+            Some(idx) => {
+                self.section = AppSection::Notes;
+                self.selected_note = Some(idx);
+                self.open_selected_item();
+            }
+            None => self.status_message = Some("Source note no longer exists".to_string()),
+        }
     }
 
-    fn save_data_with_status(&mut self) {
+    /// Replaces `created_at` values that aren't valid RFC3339 with a
+    /// best-effort parse of the legacy value, or the current time.
+    fn fix_dates(&mut self) {
+        let timestamps = self
+            .notes
+            .iter_mut()
+            .map(|n| &mut n.created_at)
+            .chain(self.todos.iter_mut().map(|t| &mut t.created_at));
+
+        let mut fixed = 0;
+        for created_at in timestamps {
+            if let Some(repaired) = dates::repair_timestamp(created_at) {
+                *created_at = repaired;
+                fixed += 1;
+            }
+        }
+
+        if fixed > 0 {
+            self.unsaved_changes = true;
+        }
+        self.status_message = Some(format!("Fixed {} invalid timestamp(s)", fixed));
+    }
+
+    /// Copies the notes and todos so `undo_structural` can bring them back.
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            notes: self.notes.clone(),
+            todos: self.todos.clone(),
+            catalogs: self.catalogs.clone(),
+        }
+    }
+
+    pub fn undo_structural(&mut self) {
+        let Some(snapshot) = self.structural_history.pop() else {
+            self.status_message = Some("Nothing to undo".to_string());
+            return;
+        };
+
+        self.notes = snapshot.notes;
+        self.todos = snapshot.todos;
+        self.catalogs = snapshot.catalogs;
+        self.text_history.clear();
+        let clamp = |idx: Option<usize>, len: usize| idx.filter(|&i| i < len);
+        self.selected_note = clamp(self.selected_note, self.notes.len());
+        self.current_note = clamp(self.current_note, self.notes.len());
+        self.selected_todo = clamp(self.selected_todo, self.todos.len());
+        self.current_todo = clamp(self.current_todo, self.todos.len());
+        self.selected_catalog = clamp(self.selected_catalog, self.catalogs.len());
+        self.current_catalog = clamp(self.current_catalog, self.catalogs.len());
+        self.reselect_visible_note();
+        self.reselect_visible_todo();
+        let order = self.catalog_display_order();
+        Self::reselect_visible(&order, &mut self.selected_catalog, &mut self.current_catalog);
+        self.unsaved_changes = true;
+        self.status_message = Some("Undid last change".to_string());
+    }
+
+    /// Single place deciding whether a quit request (`:q`, `:q!`, Ctrl+Q)
+    /// ends the session. `run_app` breaks out of its loop once
+    /// `should_quit` is set.
+    pub fn request_quit(&mut self, force: bool) {
+        if force {
+            // Clearing the flag keeps the exit path in main from saving the
+            // changes the user just asked to discard.
+            self.unsaved_changes = false;
+            self.should_quit = true;
+        } else if self.unsaved_changes {
+            if matches!(self.mode, AppMode::QuitPrompt) {
+                return;
+            }
+            self.mode_before_command = None;
+            let previous = std::mem::replace(&mut self.mode, AppMode::QuitPrompt);
+            self.mode_before_quit = Some(match previous {
+                AppMode::Command => AppMode::Normal,
+                mode => mode,
+            });
+            self.status_message = Some(
+                "Unsaved changes: (s)ave and quit, (d)iscard and quit, (c)ancel".to_string(),
+            );
+        } else {
+            self.should_quit = true;
+        }
+    }
+
+    /// Quit prompt: save, and quit only if that worked.
+    pub fn save_and_quit(&mut self) {
         match self.save_data() {
             Ok(_) => {
-                self.status_message = Some("Data saved successfully".to_string());
+                log::info!("saved {} notes and {} todos", self.notes.len(), self.todos.len());
                 self.unsaved_changes = false;
+                self.should_quit = true;
+            }
+            Err(err) => {
+                log::error!("save failed: {}", err);
+                self.cancel_quit();
+                self.status_message = Some(format!("Save failed ({}); not quitting", err));
             }
-            Err(err) => self.status_message = Some(format!("Error saving data: {}", err)),
         }
     }
 
-    fn backup_data_with_status(&mut self) {
-        match self.backup_data() {
-            Ok(path) => {
-                self.status_message = Some(format!("Backup created at: {:?}", path));
+    pub fn cancel_quit(&mut self) {
+        self.mode = self.mode_before_quit.take().unwrap_or(AppMode::Normal);
+        self.status_message = None;
+    }
+}
+
+/// Treats a leading `~/` in a typed path as the home directory.
+fn expand_home(arg: &str) -> PathBuf {
+    match arg.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None => PathBuf::from(arg),
+    }
+}
+
+/// Where `:export-md`/`:export-csv` (and `--export` without `--out`)
+/// write: `terminal_notes_export.md` or `.csv` in the home directory.
+pub fn default_export_path(format: &str) -> PathBuf {
+    let extension = if format == "csv" { "csv" } else { "md" };
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(format!("terminal_notes_export.{}", extension))
+}
+
+/// `title`, or `title (2)`, `title (3)`, ... if `existing` already has it.
+fn unique_title<'a>(existing: impl Iterator<Item = &'a str> + Clone, title: &str) -> String {
+    let taken = |candidate: &str| existing.clone().any(|t| t == candidate);
+    if !taken(title) {
+        return title.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", title, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_else(|| title.to_string())
+}
+
+/// `titles` with each repeat of an earlier title replaced by the first free
+/// `title (n)` not used anywhere in the list, so the first holder of a name
+/// keeps it and the result does not depend on anything but the order.
+/// Titles are compared case-sensitively.
+fn disambiguate_titles(titles: &[String]) -> Vec<String> {
+    let mut result = titles.to_vec();
+    for i in 0..result.len() {
+        if result[..i].contains(&result[i]) {
+            let title = unique_title(result.iter().map(String::as_str), &result[i]);
+            result[i] = title;
+        }
+    }
+    result
+}
+
+/// Lowercased, whitespace-collapsed title and content used to spot
+/// duplicates that differ only in formatting.
+fn dedup_key(title: &str, content: &str) -> String {
+    let normalize =
+        |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+    format!("{}\n{}", normalize(title), normalize(content))
+}
+
+/// Returns the ascending indices of entries whose key matches another entry,
+/// keeping the oldest entry of each group by `created_at`.
+fn duplicate_indices<'a>(entries: impl Iterator<Item = (String, &'a str)>) -> Vec<usize> {
+    let created = |ts: &str| DateTime::parse_from_rfc3339(ts).ok();
+    let mut keepers: HashMap<String, (usize, &'a str)> = HashMap::new();
+    let mut duplicates = Vec::new();
+
+    for (i, (key, created_at)) in entries.enumerate() {
+        match keepers.get_mut(&key) {
+            Some(kept) => {
+                let older = matches!(
+                    (created(created_at), created(kept.1)),
+                    (Some(new), Some(old)) if new < old
+                );
+                if older {
+                    duplicates.push(kept.0);
+                    *kept = (i, created_at);
+                } else {
+                    duplicates.push(i);
+                }
             }
-            Err(err) => {
-                self.status_message = Some(format!("Error creating backup: {}", err));
+            None => {
+                keepers.insert(key, (i, created_at));
             }
         }
     }
+    duplicates.sort_unstable();
+    duplicates
+}
 
-    fn export_data_with_status(&mut self, format: &str) {
-        let extension = if format == "markdown" { "md" } else { "csv" };
-        let path = dirs::home_dir()
-            .unwrap_or_else(|| std::path::PathBuf::from("."))
-            .join(format!("terminal_notes_export.{}", extension));
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::tests::temp_data_dir;
 
-        match self.export_data(format, &path) {
-            Ok(_) => self.status_message = Some(format!("Exported to: {:?}", path)),
-            Err(err) => {
-                self.status_message =
-                    Some(format!("Error exporting to {}: {}", format, err))
+    fn test_app(name: &str) -> App {
+        App::with_file_io(FileIO::open(temp_data_dir(name)).unwrap()).unwrap()
+    }
+
+    fn run(app: &mut App, command: &str) {
+        app.command_buffer = command.to_string();
+        app.mode = AppMode::Command;
+        app.execute_command();
+    }
+
+    #[test]
+    fn corrupt_data_is_set_aside() {
+        let dir = temp_data_dir("corrupt");
+        std::fs::write(dir.join("data.json"), "{ not json").unwrap();
+
+        let app = App::with_file_io(FileIO::open(dir.clone()).unwrap()).unwrap();
+        assert!(app.notes.is_empty() && app.todos.is_empty());
+        assert!(app.status_message.as_deref().unwrap_or_default().contains("data.corrupt."));
+
+        assert!(!dir.join("data.json").exists());
+        let moved: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with("data.corrupt.") && name.ends_with(".json"))
+            .collect();
+        assert_eq!(moved.len(), 1);
+        let timestamp = &moved[0]["data.corrupt.".len()..moved[0].len() - ".json".len()];
+        assert!(chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%d_%H%M%S").is_ok());
+        assert_eq!(std::fs::read_to_string(dir.join(&moved[0])).unwrap(), "{ not json");
+    }
+
+    #[test]
+    fn quit_without_changes_quits() {
+        let mut app = test_app("quit-clean");
+        run(&mut app, "q");
+        assert!(app.should_quit);
+    }
+
+    #[test]
+    fn quit_with_changes_prompts() {
+        let mut app = test_app("quit-dirty");
+        run(&mut app, "nn-blank");
+        run(&mut app, "q");
+        assert!(!app.should_quit);
+        assert!(matches!(app.mode, AppMode::QuitPrompt));
+        assert!(app.unsaved_changes);
+    }
+
+    #[test]
+    fn force_quit_discards_changes() {
+        let mut app = test_app("quit-force");
+        run(&mut app, "nn-blank");
+        run(&mut app, "q!");
+        assert!(app.should_quit);
+        assert!(!app.unsaved_changes);
+    }
+
+    fn navigate_all(app: &mut App) {
+        app.next_note();
+        app.previous_note();
+        app.next_todo();
+        app.previous_todo();
+        app.next_catalog();
+        app.previous_catalog();
+    }
+
+    #[test]
+    fn navigating_after_purging_everything_selects_nothing() {
+        let mut app = test_app("purge-all");
+        run(&mut app, "3nn-blank");
+        run(&mut app, "2ntodo");
+        run(&mut app, "nc https://example.com");
+        for section in [AppSection::Notes, AppSection::Todos, AppSection::Catalog] {
+            app.section = section;
+            for _ in 0..3 {
+                run(&mut app, "purge");
             }
         }
+        assert!(app.notes.is_empty() && app.todos.is_empty() && app.catalogs.is_empty());
+
+        navigate_all(&mut app);
+        assert_eq!(app.selected_note, None);
+        assert_eq!(app.selected_todo, None);
+        assert_eq!(app.selected_catalog, None);
     }
 
-    fn handle_quit(&mut self) {
-        if self.unsaved_changes {
-            self.status_message =
-                Some("Unsaved changes! Use :save first or :q! to force quit".to_string());
+    #[test]
+    fn navigating_after_archiving_everything_selects_nothing() {
+        let mut app = test_app("archive-all");
+        run(&mut app, "2nn-blank");
+        run(&mut app, "2ntodo");
+        for section in [AppSection::Notes, AppSection::Todos] {
+            app.section = section;
+            for _ in 0..2 {
+                app.next_note();
+                app.next_todo();
+                run(&mut app, "archive");
+            }
+        }
+        assert!(app.notes.iter().all(|n| n.archived) && app.todos.iter().all(|t| t.archived));
+
+        navigate_all(&mut app);
+        assert_eq!(app.selected_note, None);
+        assert_eq!(app.selected_todo, None);
+    }
+
+    #[test]
+    fn navigating_with_stale_indices_selects_nothing() {
+        let mut app = test_app("stale-index");
+        app.selected_note = Some(4);
+        app.current_note = Some(4);
+        app.selected_todo = Some(2);
+        app.selected_catalog = Some(7);
+
+        navigate_all(&mut app);
+        assert_eq!(app.selected_note, None);
+        assert_eq!(app.current_note, None);
+        assert_eq!(app.selected_todo, None);
+        assert_eq!(app.selected_catalog, None);
+    }
+
+    fn titles(list: &[&str]) -> Vec<String> {
+        list.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn disambiguate_titles_numbers_repeats_in_order() {
+        let input = titles(&["Plan", "Plan", "Ideas", "Plan"]);
+        let expected = titles(&["Plan", "Plan (2)", "Ideas", "Plan (3)"]);
+        assert_eq!(disambiguate_titles(&input), expected);
+        assert_eq!(disambiguate_titles(&input), disambiguate_titles(&input));
+        assert_eq!(disambiguate_titles(&expected), expected);
+    }
+
+    #[test]
+    fn disambiguate_titles_skips_suffixes_already_in_use() {
+        assert_eq!(
+            disambiguate_titles(&titles(&["Plan", "Plan (2)", "Plan"])),
+            titles(&["Plan", "Plan (2)", "Plan (3)"])
+        );
+        // A later "Plan (2)" keeps its name; the repeat takes the next free one.
+        assert_eq!(
+            disambiguate_titles(&titles(&["Plan", "Plan", "Plan (2)"])),
+            titles(&["Plan", "Plan (3)", "Plan (2)"])
+        );
+        assert_eq!(
+            disambiguate_titles(&titles(&["Plan (2)", "Plan (2)"])),
+            titles(&["Plan (2)", "Plan (2) (2)"])
+        );
+    }
+
+    #[test]
+    fn disambiguate_titles_is_case_sensitive() {
+        let input = titles(&["Plan", "plan", "PLAN"]);
+        assert_eq!(disambiguate_titles(&input), input);
+        assert_eq!(
+            disambiguate_titles(&titles(&["plan", "Plan", "plan"])),
+            titles(&["plan", "Plan", "plan (2)"])
+        );
+    }
+
+    #[test]
+    fn spawned_todo_links_to_its_note_despite_shared_timestamps() {
+        let mut app = test_app("source-link");
+        let markdown = app.file_io.data_dir().join("import.md");
+        std::fs::write(&markdown, "# First\none\n# Second\ntwo\n").unwrap();
+        run(&mut app, &format!("import-md {}", markdown.display()));
+        assert_eq!(app.notes.len(), 2);
+        assert_eq!(app.notes[0].created_at, app.notes[1].created_at);
+        assert_ne!(app.notes[0].id, app.notes[1].id);
+
+        app.section = AppSection::Notes;
+        app.selected_note = Some(1);
+        run(&mut app, "spawn-todo");
+        app.selected_note = Some(0);
+        run(&mut app, "source");
+        assert!(matches!(app.section, AppSection::Notes));
+        assert_eq!(app.selected_note, Some(1));
+    }
+
+    #[test]
+    fn purged_source_note_ids_are_not_reused() {
+        let mut app = test_app("source-purged");
+        run(&mut app, "2nn-blank");
+        app.section = AppSection::Notes;
+        app.selected_note = Some(1);
+        run(&mut app, "spawn-todo");
+        app.section = AppSection::Notes;
+        app.selected_note = Some(1);
+        run(&mut app, "purge");
+        run(&mut app, "nn-blank");
+
+        app.section = AppSection::Todos;
+        app.selected_todo = Some(0);
+        run(&mut app, "source");
+        assert!(matches!(app.section, AppSection::Todos));
+        assert_eq!(app.status_message.as_deref(), Some("Source note no longer exists"));
+    }
+
+    #[test]
+    fn archiving_duplicate_notes_keeps_a_visible_selection() {
+        let mut app = test_app("dedup-select");
+        run(&mut app, "3nn-blank");
+        app.notes[0].content = "archived".to_string();
+        app.notes[0].archived = true;
+        for note in &mut app.notes[1..] {
+            note.title = "Same".to_string();
+        }
+
+        app.section = AppSection::Notes;
+        run(&mut app, "dedup");
+        app.confirm_pending_action();
+        assert_eq!(app.notes.len(), 3);
+        assert!(app.notes[2].archived && !app.notes[1].archived);
+        assert_eq!(app.selected_note, Some(1));
+    }
+
+    #[test]
+    fn dedent_updates_the_timestamp_and_is_undoable() {
+        let mut app = test_app("dedent");
+        run(&mut app, "nn");
+        let stale = "2024-01-01T00:00:00+00:00".to_string();
+        app.notes[0].content = "    indented".to_string();
+        app.notes[0].updated_at = stale.clone();
+        app.cursor_pos = 6;
+
+        app.dedent_line();
+        assert_eq!(app.notes[0].content, "indented");
+        assert_eq!(app.cursor_pos, 2);
+        assert_ne!(app.notes[0].updated_at, stale);
+
+        app.undo_edit();
+        assert_eq!(app.notes[0].content, "    indented");
+    }
+
+    #[test]
+    fn structural_undo_moves_off_hidden_notes() {
+        let mut app = test_app("undo-hidden");
+        run(&mut app, "2nn-blank");
+        app.notes[0].archived = true;
+        let snapshot = app.snapshot();
+        app.structural_history.push(snapshot);
+        app.notes[0].archived = false;
+        app.selected_note = Some(0);
+        app.current_note = Some(0);
+
+        app.undo_structural();
+        assert_eq!(app.selected_note, Some(1));
+        assert_eq!(app.current_note, None);
+    }
+
+    #[test]
+    fn invalid_tags_are_command_errors_and_change_nothing() {
+        let mut app = test_app("tag-invalid");
+        run(&mut app, "nn-blank");
+        app.unsaved_changes = false;
+
+        let err = app.run_command("tag", "good, bad;tag", 1).unwrap_err();
+        assert!(matches!(err, CommandError::InvalidArgument { command, .. } if command == "tag"));
+        assert!(app.notes[0].tags.is_empty());
+        assert!(!app.unsaved_changes);
+    }
+
+    #[test]
+    fn tags_mark_changes_only_when_the_list_changes() {
+        let mut app = test_app("tag-changes");
+        run(&mut app, "nn-blank");
+        let stale = "2024-01-01T00:00:00+00:00".to_string();
+        app.notes[0].updated_at = stale.clone();
+        app.unsaved_changes = false;
+
+        run(&mut app, "untag missing");
+        assert!(!app.unsaved_changes);
+        assert_eq!(app.notes[0].updated_at, stale);
+
+        run(&mut app, "tag work");
+        assert!(app.unsaved_changes);
+        assert_ne!(app.notes[0].updated_at, stale);
+
+        app.notes[0].updated_at = stale.clone();
+        app.unsaved_changes = false;
+        run(&mut app, "tag work");
+        assert!(!app.unsaved_changes);
+        assert_eq!(app.notes[0].updated_at, stale);
+        assert_eq!(app.status_message.as_deref(), Some("Tags: work"));
+    }
+
+    #[test]
+    fn encrypting_keeps_command_history_off_disk() {
+        let mut app = test_app("history-encrypt");
+        let history = app.file_io.data_dir().join("command_history");
+        let record = |app: &mut App, command: &str| {
+            app.command_buffer = command.to_string();
+            app.record_command();
+            run(app, command);
+        };
+        record(&mut app, "nn-blank");
+        record(&mut app, "tag before-secret");
+        assert!(std::fs::read_to_string(&history).unwrap().contains("before-secret"));
+
+        app.enable_encryption("passphrase".to_string());
+        assert!(!history.exists());
+
+        record(&mut app, "tag after-secret");
+        record(&mut app, "find after-secret");
+        assert!(!history.exists());
+        assert!(app.command_history.iter().any(|c| c.contains("after-secret")));
+    }
+
+    #[test]
+    fn dedup_ignores_archived_copies() {
+        let mut app = test_app("dedup-archived");
+        run(&mut app, "2nn-blank");
+        for note in &mut app.notes {
+            note.title = "Same".to_string();
+        }
+        app.notes[0].archived = true;
+        app.notes[1].created_at = "2030-01-01T00:00:00+00:00".to_string();
+
+        app.section = AppSection::Notes;
+        run(&mut app, "dedup");
+        assert!(app.pending_action.is_none());
+        assert_eq!(app.status_message.as_deref(), Some("No duplicate notes found"));
+    }
+
+    #[test]
+    fn purging_before_the_open_item_keeps_it_open() {
+        let mut app = test_app("purge-shift");
+        run(&mut app, "3nn-blank");
+        run(&mut app, "2ntodo");
+        app.notes[2].content = "open note".to_string();
+        app.todos[1].content = "open todo".to_string();
+        app.current_note = Some(2);
+        app.current_todo = Some(1);
+
+        app.section = AppSection::Notes;
+        app.selected_note = Some(0);
+        run(&mut app, "purge");
+        assert_eq!(app.current_note, Some(1));
+        assert_eq!(app.notes[1].content, "open note");
+
+        app.section = AppSection::Todos;
+        app.selected_todo = Some(0);
+        run(&mut app, "purge");
+        assert_eq!(app.current_todo, Some(0));
+        assert_eq!(app.todos[0].content, "open todo");
+
+        app.section = AppSection::Notes;
+        app.selected_note = Some(1);
+        run(&mut app, "purge");
+        assert_eq!(app.current_note, None);
+    }
+
+    #[test]
+    fn deleting_catalog_entries_and_leaving_keep_the_open_entry_right() {
+        let mut app = test_app("catalog-shift");
+        for url in ["https://a.example", "https://b.example", "https://c.example"] {
+            run(&mut app, &format!("nc {}", url));
         }
+        app.current_catalog = Some(2);
+        app.selected_catalog = Some(0);
+        run(&mut app, "del");
+        assert_eq!(app.current_catalog, Some(1));
+        assert_eq!(app.catalogs[1].url, "https://c.example");
+
+        app.selected_catalog = Some(1);
+        run(&mut app, "del");
+        assert_eq!(app.current_catalog, None);
+
+        app.current_catalog = Some(0);
+        run(&mut app, "mm");
+        assert_eq!(app.current_catalog, None);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn failed_save_and_quit_reports_the_error() {
+        let mut app = test_app("save-quit-fails");
+        run(&mut app, "nn-blank");
+        run(&mut app, "q");
+        assert!(matches!(app.mode, AppMode::QuitPrompt));
+        // A directory where the temporary file goes makes the write fail.
+        std::fs::create_dir(app.file_io.data_dir().join("data.json.tmp")).unwrap();
+
+        app.save_and_quit();
+        assert!(!app.should_quit);
+        assert!(app.unsaved_changes);
+        let status = app.status_message.as_deref().unwrap_or_default();
+        assert!(status.starts_with("Save failed (") && status.ends_with("); not quitting"));
+        assert!(status.len() > "Save failed (); not quitting".len());
+    }
+}
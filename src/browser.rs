@@ -0,0 +1,57 @@
+// src/browser.rs
+use std::{
+    io,
+    process::{Command, Stdio},
+};
+
+/// Whether `url` is an absolute http(s) URL with a host and no whitespace
+/// or control characters, i.e. safe to hand to the system opener.
+pub fn looks_like_url(url: &str) -> bool {
+    let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("");
+    !host.is_empty() && !url.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// Opens `url` in the default browser using the platform's opener.
+pub fn open(url: &str) -> Result<(), io::Error> {
+    if !looks_like_url(url) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not an http(s) URL", url),
+        ));
+    }
+
+    // `rundll32` avoids `cmd /C start`, which would interpret `&` in the URL.
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        ("rundll32", &["url.dll,FileProtocolHandler"])
+    } else {
+        ("xdg-open", &[])
+    };
+
+    // The opener's output would scribble over the TUI.
+    let status = Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(err.kind(), format!("{} not found", program))
+            }
+            _ => err,
+        })?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("browser launcher exited with {}", status)))
+    }
+}
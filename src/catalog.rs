@@ -1,7 +1,7 @@
 // src/catalog.rs
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct CatalogItem {
     pub name: String,
     pub description: String,
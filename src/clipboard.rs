@@ -0,0 +1,94 @@
+// src/clipboard.rs
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Clipboard writers to try in order, as program plus arguments.
+fn copy_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if cfg!(target_os = "macos") {
+        commands.push(("pbcopy", &[]));
+    } else if cfg!(windows) {
+        commands.push(("clip", &[]));
+    } else {
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-copy", &[]));
+        }
+        commands.push(("xclip", &["-selection", "clipboard"]));
+        commands.push(("xsel", &["--clipboard", "--input"]));
+    }
+    commands
+}
+
+/// Clipboard readers to try in order, as program plus arguments.
+fn paste_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut commands: Vec<(&'static str, &'static [&'static str])> = Vec::new();
+    if cfg!(target_os = "macos") {
+        commands.push(("pbpaste", &[]));
+    } else if cfg!(windows) {
+        commands.push(("powershell", &["-NoProfile", "-Command", "Get-Clipboard -Raw"]));
+    } else {
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(("wl-paste", &["--no-newline"]));
+        }
+        commands.push(("xclip", &["-selection", "clipboard", "-out"]));
+        commands.push(("xsel", &["--clipboard", "--output"]));
+    }
+    commands
+}
+
+/// Copies `text` to the system clipboard using the first available
+/// platform utility.
+pub fn copy(text: &str) -> Result<(), io::Error> {
+    for (program, args) in copy_commands() {
+        let Ok(mut child) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        else {
+            continue;
+        };
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+        }
+        if child.wait()?.success() {
+            return Ok(());
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard utility available",
+    ))
+}
+
+/// Reads text from the system clipboard using the first platform utility
+/// that succeeds. Line endings are normalized to `\n`.
+pub fn paste() -> Result<String, io::Error> {
+    for (program, args) in paste_commands() {
+        let Ok(output) = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+        else {
+            continue;
+        };
+
+        if output.status.success() {
+            let text = String::from_utf8(output.stdout)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "clipboard isn't text"))?;
+            return Ok(text.replace("\r\n", "\n"));
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no clipboard utility available",
+    ))
+}
@@ -0,0 +1,334 @@
+// src/commands.rs
+
+/// A command accepted in command mode. `execute_command` dispatches on
+/// these names; the help screen and Tab completion are built from this
+/// table so the three stay in sync.
+pub struct CommandInfo {
+    /// Primary name first, then aliases.
+    pub names: &'static [&'static str],
+    /// Argument synopsis shown after the names in help.
+    pub args: &'static str,
+    pub summary: &'static str,
+}
+
+pub const COMMANDS: &[CommandInfo] = &[
+    CommandInfo { names: &["nn"], args: "", summary: "Create new notes ([n]nn for several)" },
+    CommandInfo { names: &["nn-blank"], args: "", summary: "Create notes without editing" },
+    CommandInfo { names: &["ntodo"], args: "", summary: "Create new todos ([n]ntodo for several)" },
+    CommandInfo { names: &["nc"], args: "<url> [name]", summary: "Add a catalog entry (bookmark)" },
+    CommandInfo { names: &["open"], args: "", summary: "Open the selected catalog entry's URL" },
+    CommandInfo { names: &["del"], args: "", summary: "Archive items ([n]del for several)" },
+    CommandInfo { names: &["archive"], args: "", summary: "Archive the selected note or todo" },
+    CommandInfo { names: &["unarchive"], args: "", summary: "Restore an archived item" },
+    CommandInfo { names: &["archived"], args: "", summary: "Toggle the view of archived items" },
+    CommandInfo { names: &["purge"], args: "", summary: "Delete the selected item for good" },
+    CommandInfo { names: &["mm"], args: "", summary: "Go to main menu" },
+    CommandInfo { names: &["?"], args: "", summary: "Show this help" },
+    CommandInfo {
+        names: &["save", "w"],
+        args: "[path]",
+        summary: "Save all data (to path: save a copy)",
+    },
+    CommandInfo { names: &["rnm"], args: "", summary: "Rename the selected item" },
+    CommandInfo { names: &["backup"], args: "", summary: "Create a backup" },
+    CommandInfo {
+        names: &["restore"],
+        args: "",
+        summary: "Pick a backup to restore (current data is backed up first)",
+    },
+    CommandInfo {
+        names: &["import"],
+        args: "<path>",
+        summary: "Append notes/todos from another data file",
+    },
+    CommandInfo {
+        names: &["import-md"],
+        args: "<path>",
+        summary: "Import a Markdown file, one note per #/## heading",
+    },
+    CommandInfo {
+        names: &["export-md", "export-markdown"],
+        args: "[--all]",
+        summary: "Export the listed items to Markdown",
+    },
+    CommandInfo {
+        names: &["export-csv"],
+        args: "[--all]",
+        summary: "Export the listed items to CSV",
+    },
+    CommandInfo {
+        names: &["export-one"],
+        args: "<fmt>",
+        summary: "Export the selected item (markdown/csv/txt)",
+    },
+    CommandInfo { names: &["reflow"], args: "[w]", summary: "Rewrap note paragraphs (default 80)" },
+    CommandInfo {
+        names: &["s"],
+        args: "/old/new/[g]",
+        summary: "Replace text in the open note or todo",
+    },
+    CommandInfo { names: &["trim"], args: "", summary: "Trim whitespace in the current note" },
+    CommandInfo { names: &["trim-all"], args: "", summary: "Trim whitespace in every note" },
+    CommandInfo {
+        names: &["dedup"],
+        args: "[titles]",
+        summary: "Archive duplicate notes/todos (asks y/n)",
+    },
+    CommandInfo { names: &["zen"], args: "", summary: "Distraction-free editing (Esc exits)" },
+    CommandInfo { names: &["preview"], args: "", summary: "Toggle rendered Markdown for the note" },
+    CommandInfo { names: &["fix-dates"], args: "", summary: "Repair invalid creation timestamps" },
+    CommandInfo {
+        names: &["undo"],
+        args: "",
+        summary: "Undo the last delete, sort, dedup or bulk edit (also u)",
+    },
+    CommandInfo {
+        names: &["goto"],
+        args: "",
+        summary: "Jump to a note or todo by fuzzy title (Ctrl+P)",
+    },
+    CommandInfo { names: &["find"], args: "<q>", summary: "Search titles and content" },
+    CommandInfo { names: &["tag"], args: "<a,b>", summary: "Add tags to the selected item" },
+    CommandInfo { names: &["untag"], args: "<a,b>", summary: "Remove tags from the selected item" },
+    CommandInfo {
+        names: &["done"],
+        args: "[n]",
+        summary: "Complete n todos from the selection ([n]done too)",
+    },
+    CommandInfo { names: &["cal"], args: "", summary: "Pick the todo's due date on a calendar" },
+    CommandInfo {
+        names: &["due"],
+        args: "<date|clear>",
+        summary: "Set a todo's due date (YYYY-MM-DD or clear)",
+    },
+    CommandInfo {
+        names: &["set"],
+        args: "<key> [value]",
+        summary: "Show or change a config.json setting",
+    },
+    CommandInfo { names: &["encrypt"], args: "", summary: "Encrypt data.json with a passphrase" },
+    CommandInfo { names: &["decrypt"], args: "", summary: "Store data.json unencrypted again" },
+    CommandInfo {
+        names: &["theme"],
+        args: "<name>",
+        summary: "Switch colors: dark, light or solarized",
+    },
+    CommandInfo { names: &["wrap"], args: "", summary: "Toggle line wrapping for the note" },
+    CommandInfo {
+        names: &["goal"],
+        args: "<n>",
+        summary: "Set a word goal for the note (0 clears)",
+    },
+    CommandInfo { names: &["pin"], args: "", summary: "Keep the selected note at the top" },
+    CommandInfo { names: &["unpin"], args: "", summary: "Return a pinned note to its place" },
+    CommandInfo {
+        names: &["recur"],
+        args: "<period>",
+        summary: "Repeat a todo daily/weekly/monthly (none stops)",
+    },
+    CommandInfo { names: &["inspect"], args: "", summary: "Show the selected item as JSON" },
+    CommandInfo {
+        names: &["filter"],
+        args: "<f>",
+        summary: "Show all/done/pending/overdue todos",
+    },
+    CommandInfo {
+        names: &["tagged"],
+        args: "[a,b,...]",
+        summary: "Show only items with all these tags (none clears)",
+    },
+    CommandInfo {
+        names: &["tagged-any"],
+        args: "[a,b,...]",
+        summary: "Show only items with any of these tags (none clears)",
+    },
+    CommandInfo {
+        names: &["sort"],
+        args: "<key>",
+        summary: "Sort by title/created/modified/due/severity/none",
+    },
+    CommandInfo {
+        names: &["spawn-todo"],
+        args: "",
+        summary: "Create a todo linked to the current note",
+    },
+    CommandInfo { names: &["follow"], args: "", summary: "Open the note a [[title]] link names" },
+    CommandInfo { names: &["source"], args: "", summary: "Open the note a todo was spawned from" },
+    CommandInfo { names: &["date"], args: "", summary: "Insert today's date at the cursor" },
+    CommandInfo { names: &["time"], args: "", summary: "Insert the current time at the cursor" },
+    CommandInfo {
+        names: &["alias"],
+        args: "[name] [command]",
+        summary: "Define a command alias (none lists them)",
+    },
+    CommandInfo { names: &["q", "quit"], args: "", summary: "Quit application" },
+    CommandInfo { names: &["q!"], args: "", summary: "Force quit without saving" },
+];
+
+/// Parses the `/old/new/` or `/old/new/g` part of a `:s` command into the
+/// text to find, its replacement, and whether to replace every occurrence.
+/// `\/` stands for a literal slash; the closing slash may be left off.
+pub fn parse_substitute(spec: &str) -> Option<(String, String, bool)> {
+    let mut chars = spec.strip_prefix('/')?.chars();
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('/') => current.push('/'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            '/' if parts.len() < 2 => parts.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+
+    let flags = match parts.len() {
+        2 => current,
+        1 => {
+            parts.push(current);
+            String::new()
+        }
+        _ => return None,
+    };
+    let global = match flags.as_str() {
+        "" => false,
+        "g" => true,
+        _ => return None,
+    };
+    let replacement = parts.pop()?;
+    let pattern = parts.pop()?;
+    Some((pattern, replacement, global))
+}
+
+/// Whether `name` is one of the built-in commands above.
+pub fn is_builtin(name: &str) -> bool {
+    lookup(name).is_some()
+}
+
+fn lookup(name: &str) -> Option<&'static CommandInfo> {
+    COMMANDS.iter().find(|command| command.names.contains(&name))
+}
+
+/// Why a command line was not run. `execute_command` shows it in the
+/// status bar.
+#[derive(Debug)]
+pub enum CommandError {
+    /// No built-in command or alias has this name.
+    Unknown {
+        name: String,
+        suggestion: Option<&'static str>,
+    },
+    /// The command exists but its argument is missing or malformed.
+    InvalidArgument { command: String, problem: String },
+}
+
+impl CommandError {
+    pub fn unknown(name: &str) -> Self {
+        CommandError::Unknown { name: name.to_string(), suggestion: closest_name(name) }
+    }
+
+    pub fn invalid(command: &str, problem: impl Into<String>) -> Self {
+        CommandError::InvalidArgument { command: command.to_string(), problem: problem.into() }
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandError::Unknown { name, suggestion: Some(suggestion) } => {
+                write!(f, "Unknown command: {} (did you mean :{}?)", name, suggestion)
+            }
+            CommandError::Unknown { name, suggestion: None } => {
+                write!(f, "Unknown command: {} (:? lists commands)", name)
+            }
+            CommandError::InvalidArgument { command, problem } => {
+                write!(f, "{} - usage: {}", problem, usage(command))
+            }
+        }
+    }
+}
+
+/// The usage line for `name` from the table, e.g. `:due <date|clear>`.
+pub fn usage(name: &str) -> String {
+    match lookup(name) {
+        Some(command) if command.args.starts_with('/') => format!(":{}{}", name, command.args),
+        Some(command) if !command.args.is_empty() => format!(":{} {}", name, command.args),
+        _ => format!(":{}", name),
+    }
+}
+
+/// Rejects an argument given to a command that takes none; commands
+/// with arguments check them where they parse them.
+pub fn check_arity(name: &str, arg: &str) -> Result<(), CommandError> {
+    match lookup(name) {
+        Some(command) if command.args.is_empty() && !arg.is_empty() => {
+            Err(CommandError::invalid(name, format!("{} takes no argument", name)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The built-in name nearest to a mistyped one: a name it is a prefix
+/// of, or else one at most two edits away.
+fn closest_name(name: &str) -> Option<&'static str> {
+    let names = || COMMANDS.iter().flat_map(|command| command.names.iter().copied());
+    if name.is_empty() {
+        return None;
+    }
+    names().find(|candidate| candidate.starts_with(name)).or_else(|| {
+        names()
+            .map(|candidate| (edit_distance(name, candidate), candidate))
+            .filter(|&(distance, _)| distance <= 2)
+            .min_by_key(|&(distance, _)| distance)
+            .map(|(_, candidate)| candidate)
+    })
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Every command name and alias starting with `prefix`, in table order.
+pub fn completions(prefix: &str) -> Vec<&'static str> {
+    COMMANDS
+        .iter()
+        .flat_map(|command| command.names.iter().copied())
+        .filter(|name| name.starts_with(prefix))
+        .collect()
+}
+
+/// One help line per command, e.g. `:save/:w - Save all data`.
+pub fn help_lines() -> Vec<String> {
+    COMMANDS
+        .iter()
+        .map(|command| {
+            let names = command
+                .names
+                .iter()
+                .map(|name| format!(":{}", name))
+                .collect::<Vec<_>>()
+                .join("/");
+            let usage = if command.args.is_empty() {
+                names
+            } else {
+                format!("{} {}", names, command.args)
+            };
+            format!("{:<18} - {}", usage, command.summary)
+        })
+        .collect()
+}
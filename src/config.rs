@@ -0,0 +1,136 @@
+// src/config.rs
+use crate::sort::SortKey;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// When completed todos move to the archive: "never", "immediately",
+    /// or a delay such as "30m", "12h" or "1d".
+    pub archive_completed_after: String,
+    /// Severity name ("critical", "high", ...) to a color name or `#rrggbb`.
+    pub severity_colors: HashMap<String, String>,
+    /// Selected-row style: "auto", "reversed", or a background color.
+    pub highlight: String,
+    /// Built-in color scheme: "dark", "light" or "solarized".
+    pub theme: String,
+    /// Number of spaces Tab inserts in the editor.
+    pub tab_width: usize,
+    /// Insert a literal tab character instead of spaces.
+    pub use_tabs: bool,
+    /// Auto-insert closing brackets and quotes while editing.
+    pub auto_pairs: bool,
+    /// Last sort applied to each section, re-applied at startup.
+    pub notes_sort: SortKey,
+    pub todos_sort: SortKey,
+    pub notes_sort_descending: bool,
+    pub todos_sort_descending: bool,
+    /// Backups kept by `:backup` before the oldest are deleted (0 keeps all).
+    pub max_backups: usize,
+    /// Seconds without edits before unsaved changes are written (0 disables).
+    pub autosave_seconds: u64,
+    /// Action name ("next_item", "quit", ...) to the keys that trigger it,
+    /// replacing that action's default keys.
+    pub keymap: HashMap<String, Vec<String>>,
+    /// Keep `data.json` encrypted; a passphrase is asked for at startup.
+    pub encrypt: bool,
+    /// Strip trailing whitespace and CRLF line endings from note and todo
+    /// content when writing `data.json`.
+    pub normalize_on_save: bool,
+    /// At startup, suffix notes and todos whose title repeats an earlier
+    /// one with ` (2)`, ` (3)`, ... so they can be told apart.
+    pub rename_duplicate_titles: bool,
+    /// Share of the window width given to the list pane of the notes,
+    /// todos and catalog sections, in percent (set with Ctrl+H/Ctrl+L).
+    pub list_width_percent: u16,
+    /// User command names expanded before dispatch, e.g. "el" to
+    /// "export-md"; set with `:alias`.
+    pub aliases: HashMap<String, String>,
+    /// chrono formats for the text `:date` / `:time` insert.
+    pub date_format: String,
+    pub time_format: String,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            archive_completed_after: "never".to_string(),
+            severity_colors: HashMap::new(),
+            highlight: "auto".to_string(),
+            theme: "dark".to_string(),
+            tab_width: 4,
+            use_tabs: false,
+            auto_pairs: false,
+            notes_sort: SortKey::None,
+            todos_sort: SortKey::None,
+            notes_sort_descending: false,
+            todos_sort_descending: false,
+            max_backups: 10,
+            autosave_seconds: 30,
+            keymap: HashMap::new(),
+            encrypt: false,
+            normalize_on_save: true,
+            rename_duplicate_titles: false,
+            list_width_percent: 30,
+            aliases: HashMap::new(),
+            date_format: "%Y-%m-%d".to_string(),
+            time_format: "%H:%M".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Delay after completion before a todo is archived, or `None` when
+    /// completed todos should stay in the list.
+    pub fn archive_delay(&self) -> Option<Duration> {
+        let value = self.archive_completed_after.trim();
+        match value {
+            "never" | "" => None,
+            "immediately" => Some(Duration::zero()),
+            _ => {
+                let unit_start = value.len() - value.chars().last()?.len_utf8();
+                let (amount, unit) = value.split_at(unit_start);
+                let amount = amount.parse::<i64>().ok()?;
+                match unit {
+                    "m" => Some(Duration::minutes(amount)),
+                    "h" => Some(Duration::hours(amount)),
+                    "d" => Some(Duration::days(amount)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Quiet period before an autosave, or `None` when autosave is off.
+    pub fn autosave_interval(&self) -> Option<std::time::Duration> {
+        (self.autosave_seconds > 0).then(|| std::time::Duration::from_secs(self.autosave_seconds))
+    }
+
+    /// The current value of `key` as it appears in `config.json`.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.fields().remove(key).map(|value| value.to_string())
+    }
+
+    /// A copy with `key` set to `value`, which is read as JSON when it
+    /// parses (numbers, booleans, objects) and as a plain string otherwise.
+    pub fn with_value(&self, key: &str, value: &str) -> Result<Config, String> {
+        let mut fields = self.fields();
+        if !fields.contains_key(key) {
+            return Err(format!("Unknown setting '{}'", key));
+        }
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+        fields.insert(key.to_string(), value);
+        serde_json::from_value(Value::Object(fields))
+            .map_err(|err| format!("Invalid value for {}: {}", key, err))
+    }
+
+    fn fields(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields,
+            _ => Map::new(),
+        }
+    }
+}
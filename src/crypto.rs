@@ -0,0 +1,83 @@
+// src/crypto.rs
+use std::{
+    io::{self, Read, Write},
+    process::{Command, Stdio},
+    thread,
+};
+
+/// Header `openssl enc -salt` writes before the salt and ciphertext.
+const MAGIC: &[u8] = b"Salted__";
+
+/// PBKDF2 rounds used to derive the key from the passphrase.
+const KDF_ITERATIONS: &str = "600000";
+
+/// Environment variable the passphrase is handed to `openssl` through, so
+/// it never appears on a command line.
+const PASSPHRASE_VAR: &str = "LOCUS_OPENSSL_PASSPHRASE";
+
+pub fn is_encrypted(contents: &[u8]) -> bool {
+    contents.starts_with(MAGIC)
+}
+
+/// Encrypts `plaintext` with AES-256 under a PBKDF2 key derived from
+/// `passphrase` and a random salt, using the `openssl` command.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, io::Error> {
+    run_openssl(&[], plaintext, passphrase)
+}
+
+/// Reverses `encrypt`. A wrong passphrase usually fails outright; when it
+/// happens to decrypt to garbage, parsing the result catches it instead.
+pub fn decrypt(ciphertext: &[u8], passphrase: &str) -> Result<Vec<u8>, io::Error> {
+    run_openssl(&["-d"], ciphertext, passphrase).map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => err,
+        _ => wrong_passphrase(),
+    })
+}
+
+pub fn wrong_passphrase() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "wrong passphrase or damaged data file",
+    )
+}
+
+fn run_openssl(extra_args: &[&str], input: &[u8], passphrase: &str) -> Result<Vec<u8>, io::Error> {
+    let mut child = Command::new("openssl")
+        .args(["enc", "-aes-256-cbc", "-pbkdf2", "-iter", KDF_ITERATIONS, "-salt"])
+        .args(extra_args)
+        .args(["-pass", &format!("env:{}", PASSPHRASE_VAR)])
+        .env(PASSPHRASE_VAR, passphrase)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| match err.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(io::ErrorKind::NotFound, "openssl not found")
+            }
+            _ => err,
+        })?;
+
+    // Feed stdin from another thread so a large file can't deadlock
+    // against openssl filling the stdout pipe.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+
+    let mut output = Vec::new();
+    child
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .read_to_end(&mut output)?;
+    let status = child.wait()?;
+    let written = writer
+        .join()
+        .map_err(|_| io::Error::other("openssl input thread panicked"))?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("openssl exited with {}", status)));
+    }
+    written?;
+    Ok(output)
+}
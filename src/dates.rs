@@ -0,0 +1,65 @@
+// src/dates.rs
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+/// Naive formats seen in legacy or imported `created_at` values, tried in
+/// order after RFC3339 and RFC2822.
+const LEGACY_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+];
+
+/// Returns a replacement RFC3339 timestamp when `raw` isn't valid RFC3339,
+/// or `None` when it already is. Unrecognized values become the current
+/// local time.
+pub fn repair_timestamp(raw: &str) -> Option<String> {
+    if DateTime::parse_from_rfc3339(raw).is_ok() {
+        return None;
+    }
+    Some(parse_legacy(raw.trim()).unwrap_or_else(Local::now).to_rfc3339())
+}
+
+fn parse_legacy(raw: &str) -> Option<DateTime<Local>> {
+    if let Ok(date) = DateTime::parse_from_rfc2822(raw) {
+        return Some(date.with_timezone(&Local));
+    }
+    let naive = LEGACY_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(raw, format).ok())
+        .or_else(|| {
+            ["%Y-%m-%d", "%Y/%m/%d"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Local.from_local_datetime(&naive).earliest()
+}
+
+/// Formats a stored RFC3339 timestamp for display in local time, e.g.
+/// "May 1, 2024 2:22 PM". Values that don't parse are shown as stored.
+pub fn friendly_timestamp(raw: &str) -> String {
+    match DateTime::parse_from_rfc3339(raw) {
+        Ok(date) => date.with_timezone(&Local).format("%b %-d, %Y %-I:%M %p").to_string(),
+        Err(_) => raw.to_string(),
+    }
+}
+
+/// Whether an RFC3339 timestamp falls on today's local date; unparseable
+/// values never do.
+pub fn is_today(raw: &str) -> bool {
+    DateTime::parse_from_rfc3339(raw)
+        .is_ok_and(|date| date.with_timezone(&Local).date_naive() == Local::now().date_naive())
+}
+
+/// Describes a due date `days` away from today, e.g. "in 3 days", "today"
+/// or "5 days overdue".
+pub fn relative_days(days: i64) -> String {
+    match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        d if d > 1 => format!("in {} days", d),
+        -1 => "1 day overdue".to_string(),
+        d => format!("{} days overdue", -d),
+    }
+}
@@ -0,0 +1,228 @@
+// src/editor.rs
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Clamps a byte offset to `text` and moves it back onto a char boundary so
+/// it is always safe to slice or insert at.
+pub fn clamp_to_boundary(text: &str, pos: usize) -> usize {
+    let mut pos = pos.min(text.len());
+    while !text.is_char_boundary(pos) {
+        pos -= 1;
+    }
+    pos
+}
+
+/// Closing character inserted after `open` when auto-pairing is enabled.
+pub fn closing_pair(open: char) -> Option<char> {
+    match open {
+        '(' => Some(')'),
+        '[' => Some(']'),
+        '{' => Some('}'),
+        '"' => Some('"'),
+        '`' => Some('`'),
+        _ => None,
+    }
+}
+
+/// Whether typing `c` right before the same character should step over it.
+pub fn is_closing(c: char) -> bool {
+    matches!(c, ')' | ']' | '}' | '"' | '`')
+}
+
+/// Offset of the char before `pos`, or 0 at the start of `text`.
+pub fn prev_boundary(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    text[..pos]
+        .chars()
+        .next_back()
+        .map_or(0, |c| pos - c.len_utf8())
+}
+
+/// Offset just past the char at `pos`, or `text.len()` at the end.
+pub fn next_boundary(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    text[pos..]
+        .chars()
+        .next()
+        .map_or(text.len(), |c| pos + c.len_utf8())
+}
+
+#[derive(PartialEq)]
+enum CharClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// Start of the word before `pos` for Ctrl+Left: whitespace is skipped,
+/// then a run of word characters or of punctuation.
+pub fn prev_word(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    let mut chars = text[..pos].char_indices().rev().skip_while(|&(_, c)| c.is_whitespace());
+    let Some((mut start, first)) = chars.next() else {
+        return 0;
+    };
+    let class = char_class(first);
+    for (i, c) in chars {
+        if char_class(c) != class {
+            break;
+        }
+        start = i;
+    }
+    start
+}
+
+/// Start of the next word after `pos` for Ctrl+Right: the rest of the run
+/// under the cursor is skipped, then any whitespace.
+pub fn next_word(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    let mut chars = text[pos..].char_indices().map(|(i, c)| (pos + i, c)).peekable();
+    if let Some(&(_, first)) = chars.peek() {
+        let class = char_class(first);
+        if class != CharClass::Space {
+            while chars.next_if(|&(_, c)| char_class(c) == class).is_some() {}
+        }
+    }
+    chars
+        .find(|&(_, c)| !c.is_whitespace())
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Start offset of the line containing `pos`.
+pub fn line_start(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    text[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// End offset (before the newline) of the line containing `pos`.
+pub fn line_end(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    text[pos..].find('\n').map_or(text.len(), |i| pos + i)
+}
+
+/// Column of `pos` within its line, counted in chars.
+pub fn column(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    text[line_start(text, pos)..pos].chars().count()
+}
+
+/// Offset `column` chars into the line starting at `start`, stopping at the
+/// end of that line if it is shorter.
+fn offset_at_column(text: &str, start: usize, column: usize) -> usize {
+    text[start..]
+        .char_indices()
+        .take_while(|&(_, c)| c != '\n')
+        .nth(column)
+        .map_or_else(|| line_end(text, start), |(i, _)| start + i)
+}
+
+/// Offset on the previous line at `column`, or `pos` on the first line.
+pub fn line_up(text: &str, pos: usize, column: usize) -> usize {
+    let start = line_start(text, pos);
+    if start == 0 {
+        return clamp_to_boundary(text, pos);
+    }
+    offset_at_column(text, line_start(text, start - 1), column)
+}
+
+/// Offset on the next line at `column`, or `pos` on the last line.
+pub fn line_down(text: &str, pos: usize, column: usize) -> usize {
+    let end = line_end(text, pos);
+    if end == text.len() {
+        return clamp_to_boundary(text, pos);
+    }
+    offset_at_column(text, end + 1, column)
+}
+
+/// Splits `text` into display rows no wider than `width` cells, breaking
+/// after spaces where possible and mid-word otherwise. Each row is a byte
+/// range into `text` that excludes the newline ending its line.
+pub fn wrap_rows(text: &str, width: usize) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        wrap_line(line, offset, width.max(1), &mut rows);
+        offset += line.len() + 1;
+    }
+    rows
+}
+
+fn wrap_line(line: &str, offset: usize, width: usize, rows: &mut Vec<Range<usize>>) {
+    let mut row_start = 0;
+    let mut row_width = 0;
+    let mut last_break: Option<usize> = None;
+
+    for (i, c) in line.char_indices() {
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width && i > row_start {
+            let break_at = last_break.filter(|&b| b > row_start).unwrap_or(i);
+            rows.push(offset + row_start..offset + break_at);
+            row_start = break_at;
+            row_width = line[row_start..i].width();
+            last_break = None;
+        }
+        row_width += char_width;
+        if c == ' ' {
+            last_break = Some(i + c.len_utf8());
+        }
+    }
+    rows.push(offset + row_start..offset + line.len());
+}
+
+/// One row per line of `text`, for showing it without wrapping; the same
+/// shape as `wrap_rows`.
+pub fn line_rows(text: &str) -> Vec<Range<usize>> {
+    let mut rows = Vec::new();
+    let mut offset = 0;
+    for line in text.split('\n') {
+        rows.push(offset..offset + line.len());
+        offset += line.len() + 1;
+    }
+    rows
+}
+
+/// The part of `text` after its first `columns` display cells; a wide
+/// character straddling the edge is dropped.
+pub fn skip_columns(text: &str, columns: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        if used >= columns {
+            return &text[i..];
+        }
+        used += c.width().unwrap_or(0);
+    }
+    ""
+}
+
+/// Row index and display column of byte offset `pos` among `rows` from
+/// `wrap_rows`. A cursor at a soft wrap point belongs to the following row.
+pub fn cursor_row_col(text: &str, rows: &[Range<usize>], pos: usize) -> (usize, usize) {
+    let pos = clamp_to_boundary(text, pos);
+    let row = rows
+        .iter()
+        .rposition(|row| row.start <= pos)
+        .unwrap_or(0);
+    let start = rows.get(row).map_or(0, |r| r.start);
+    (row, text[start..pos].width())
+}
+
+/// Start of the whitespace-delimited word before `pos`, skipping any
+/// whitespace between it and `pos` first.
+pub fn word_start_before(text: &str, pos: usize) -> usize {
+    let pos = clamp_to_boundary(text, pos);
+    let before = &text[..pos];
+    let word_end = before.trim_end().len();
+    before[..word_end]
+        .rfind(char::is_whitespace)
+        .map_or(0, |i| i + before[i..].chars().next().map_or(1, char::len_utf8))
+}
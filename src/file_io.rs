@@ -1,54 +1,289 @@
 // src/file_io.rs
-use crate::{note::Note, todo::Todo};
+use crate::{catalog::CatalogItem, config::Config, crypto, note::Note, todo::Todo};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::HashSet,
+    ffi::OsString,
     fs::{self, File},
     io::{self, Write},
     path::{Path, PathBuf},
 };
 
-#[derive(Serialize, Deserialize)]
+/// Longest filename stem derived from a title, in characters.
+const MAX_FILENAME_CHARS: usize = 64;
+
+/// Timestamp embedded in `backup_<timestamp>.json` filenames.
+const BACKUP_TIMESTAMP_FORMAT: &str = "%Y%m%d_%H%M%S";
+
+#[derive(Default, Serialize, Deserialize)]
 pub struct AppData {
     pub notes: Vec<Note>,
     pub todos: Vec<Todo>,
+    #[serde(default)]
+    pub catalogs: Vec<CatalogItem>,
+}
+
+impl AppData {
+    /// Fills in fields that files written by older versions lack.
+    fn backfill(mut self) -> Self {
+        for note in self.notes.iter_mut().filter(|n| n.updated_at.is_empty()) {
+            note.updated_at = note.created_at.clone();
+        }
+        for todo in self.todos.iter_mut().filter(|t| t.updated_at.is_empty()) {
+            todo.updated_at = todo.created_at.clone();
+        }
+
+        let mut seen = HashSet::new();
+        let mut next_id = next_note_id(&self.notes, &self.todos);
+        for note in self.notes.iter_mut() {
+            if note.id == 0 || !seen.insert(note.id) {
+                note.id = next_id;
+                seen.insert(next_id);
+                next_id += 1;
+            }
+        }
+        for todo in self.todos.iter_mut() {
+            if let Some(created_at) = todo.source_note.take() {
+                if todo.source_note_id.is_none() {
+                    todo.source_note_id =
+                        self.notes.iter().find(|n| n.created_at == created_at).map(|n| n.id);
+                }
+            }
+        }
+        self
+    }
+}
+
+/// The data directory for a `LOCUS_DATA_DIR` value and home directory;
+/// see `FileIO::new`.
+fn resolve_data_dir(env_dir: Option<OsString>, home: Option<PathBuf>) -> PathBuf {
+    match env_dir {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home.unwrap_or_else(|| PathBuf::from(".")).join(".terminal_notes"),
+    }
+}
+
+/// An id no note has and no todo links to, so a link to a deleted note
+/// never resolves to a newer one.
+pub fn next_note_id(notes: &[Note], todos: &[Todo]) -> u64 {
+    let linked = todos.iter().filter_map(|t| t.source_note_id);
+    notes.iter().map(|n| n.id).chain(linked).max().unwrap_or(0) + 1
+}
+
+/// Where the interface was left, saved to `session.json` on exit so the
+/// next launch reopens the same section and selection.
+#[derive(Default, Serialize, Deserialize)]
+pub struct Session {
+    /// `notes`, `todos` or `catalog`; absent when quitting from the menu.
+    #[serde(default)]
+    pub section: Option<String>,
+    #[serde(default)]
+    pub selected_note: Option<usize>,
+    #[serde(default)]
+    pub selected_todo: Option<usize>,
+    #[serde(default)]
+    pub selected_catalog: Option<usize>,
 }
 
 pub struct FileIO {
     data_dir: PathBuf,
     app_file: PathBuf,
+    config_file: PathBuf,
+    history_file: PathBuf,
+    session_file: PathBuf,
+    /// Set once the data is encrypted; `data.json` is then written through
+    /// `crypto::encrypt` and read back through `crypto::decrypt`.
+    passphrase: Option<String>,
 }
 
 impl FileIO {
+    /// Opens the data directory, creating it if needed. `LOCUS_DATA_DIR`
+    /// takes precedence when set and non-empty; otherwise it is
+    /// `~/.terminal_notes` (or `./.terminal_notes` without a home directory).
     pub fn new() -> Result<Self, io::Error> {
-        let mut data_dir = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
-        data_dir.push(".terminal_notes");
+        Self::open(resolve_data_dir(std::env::var_os("LOCUS_DATA_DIR"), dirs::home_dir()))
+    }
+
+    /// Opens (creating if needed) the given data directory.
+    pub fn open(data_dir: PathBuf) -> Result<Self, io::Error> {
         fs::create_dir_all(&data_dir)?;
 
         let app_file = data_dir.join("data.json");
-        Ok(FileIO { data_dir, app_file })
+        let config_file = data_dir.join("config.json");
+        let history_file = data_dir.join("command_history");
+        let session_file = data_dir.join("session.json");
+        Ok(FileIO {
+            data_dir,
+            app_file,
+            config_file,
+            history_file,
+            session_file,
+            passphrase: None,
+        })
+    }
+
+    /// Reads `config.json`, falling back to defaults when the file is
+    /// missing or can't be parsed so a bad config never blocks startup.
+    pub fn load_config(&self) -> Config {
+        let Ok(contents) = fs::read_to_string(&self.config_file) else {
+            return Config::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            log::warn!("ignoring malformed config {:?}: {}", self.config_file, err);
+            Config::default()
+        })
+    }
+
+    pub fn save_config(&self, config: &Config) -> Result<(), io::Error> {
+        let json = serde_json::to_string_pretty(config)?;
+        fs::write(&self.config_file, json)?;
+        Ok(())
+    }
+
+    /// Previously executed commands, oldest first; empty if none were saved.
+    pub fn load_command_history(&self) -> Vec<String> {
+        fs::read_to_string(&self.history_file)
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// Writes the command history, unless the data is encrypted: commands
+    /// carry note text, so the history then stays in memory only.
+    pub fn save_command_history(&self, history: &[String]) -> Result<(), io::Error> {
+        if self.is_encrypting() {
+            return Ok(());
+        }
+        let mut contents = history.join("\n");
+        contents.push('\n');
+        write_atomic(&self.history_file, contents.as_bytes())
+    }
+
+    /// Deletes the saved command history, if any.
+    pub fn remove_command_history(&self) -> Result<(), io::Error> {
+        match fs::remove_file(&self.history_file) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// The last saved session; the default (the main menu) if there is
+    /// none or it can't be read.
+    pub fn load_session(&self) -> Session {
+        fs::read_to_string(&self.session_file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_session(&self, session: &Session) -> Result<(), io::Error> {
+        let json = serde_json::to_string_pretty(session)?;
+        write_atomic(&self.session_file, json.as_bytes())
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    pub fn set_passphrase(&mut self, passphrase: Option<String>) {
+        self.passphrase = passphrase;
     }
 
-    pub fn save_data(&self, notes: &[Note], todos: &[Todo]) -> Result<(), io::Error> {
-        let app_data = AppData {
+    pub fn is_encrypting(&self) -> bool {
+        self.passphrase.is_some()
+    }
+
+    /// Encrypts serialized data when a passphrase is set.
+    fn encode(&self, json: &[u8]) -> Result<Vec<u8>, io::Error> {
+        match &self.passphrase {
+            Some(passphrase) => crypto::encrypt(json, passphrase),
+            None => Ok(json.to_vec()),
+        }
+    }
+
+    /// Parses a data file's raw contents, decrypting them first if needed.
+    /// An encrypted file that can't be opened is `PermissionDenied`, never
+    /// `InvalidData`, so it isn't mistaken for corruption and set aside.
+    fn decode(&self, contents: &[u8]) -> Result<AppData, io::Error> {
+        if !crypto::is_encrypted(contents) {
+            return serde_json::from_slice(contents)
+                .map(AppData::backfill)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err));
+        }
+        let Some(passphrase) = &self.passphrase else {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "data file is encrypted",
+            ));
+        };
+        let json = crypto::decrypt(contents, passphrase)?;
+        serde_json::from_slice(&json)
+            .map(AppData::backfill)
+            .map_err(|_| crypto::wrong_passphrase())
+    }
+
+    pub fn save_data(
+        &self,
+        notes: &[Note],
+        todos: &[Todo],
+        catalogs: &[CatalogItem],
+        normalize: bool,
+    ) -> Result<(), io::Error> {
+        self.save_data_to(&self.app_file, notes, todos, catalogs, normalize)
+    }
+
+    /// Writes the data in the same format as `data.json` (encrypted too,
+    /// when a passphrase is set) to `path`, which `load_data` and `:import`
+    /// can read back.
+    pub fn save_data_to(
+        &self,
+        path: &Path,
+        notes: &[Note],
+        todos: &[Todo],
+        catalogs: &[CatalogItem],
+        normalize: bool,
+    ) -> Result<(), io::Error> {
+        let mut app_data = AppData {
             notes: notes.to_vec(),
             todos: todos.to_vec(),
+            catalogs: catalogs.to_vec(),
         };
+        // Only the written copy is cleaned up, so autosave never eats the
+        // space just typed before the next word.
+        if normalize {
+            for note in &mut app_data.notes {
+                note.content = normalize_content(&note.content);
+            }
+            for todo in &mut app_data.todos {
+                todo.content = normalize_content(&todo.content);
+            }
+        }
         let json = serde_json::to_string_pretty(&app_data)?;
-        fs::write(&self.app_file, json)?;
-        Ok(())
+        write_atomic(path, &self.encode(json.as_bytes())?)
     }
 
-    pub fn load_data(&self) -> Result<(Vec<Note>, Vec<Todo>), io::Error> {
+    pub fn load_data(&self) -> Result<AppData, io::Error> {
         if !self.app_file.exists() {
-            return Ok((Vec::new(), Vec::new()));
+            return Ok(AppData::default());
         }
 
-        let contents = fs::read_to_string(&self.app_file)?;
-        let app_data: AppData = serde_json::from_str(&contents)?;
-        Ok((app_data.notes, app_data.todos))
+        // Parse failures (including truncated files) are reported as
+        // `InvalidData` so callers can tell corruption from I/O errors.
+        self.decode(&fs::read(&self.app_file)?)
+    }
+
+    /// Moves an unreadable `data.json` aside to `data.corrupt.<timestamp>.json`
+    /// so the next save can't overwrite it, and returns the new path.
+    pub fn quarantine_data(&self) -> Result<PathBuf, io::Error> {
+        let timestamp = chrono::Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+        let path = unique_path(&self.data_dir, &format!("data.corrupt.{}", timestamp), "json");
+        fs::rename(&self.app_file, &path)?;
+        Ok(path)
     }
 
-    pub fn backup_data(&self) -> Result<PathBuf, io::Error> {
+    /// Copies `data.json` to a new `backup_<timestamp>.json`, then deletes
+    /// the oldest backups beyond `max_backups` (0 keeps every backup).
+    /// Returns the new backup and the paths that were removed.
+    pub fn backup_data(&self, max_backups: usize) -> Result<(PathBuf, Vec<PathBuf>), io::Error> {
         if !self.app_file.exists() {
             return Err(io::Error::new(
                 io::ErrorKind::NotFound,
@@ -56,12 +291,73 @@ impl FileIO {
             ));
         }
 
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let backup_file = self
-            .data_dir
-            .join(format!("backup_{}.json", timestamp));
+        let timestamp = chrono::Local::now().format(BACKUP_TIMESTAMP_FORMAT);
+        // Two backups in the same second (e.g. `:restore`'s safety backup)
+        // must not overwrite each other.
+        let backup_file = unique_path(&self.data_dir, &format!("backup_{}", timestamp), "json");
         fs::copy(&self.app_file, &backup_file)?;
-        Ok(backup_file)
+
+        let mut removed = Vec::new();
+        if max_backups > 0 {
+            for old in self.list_backups()?.into_iter().skip(max_backups) {
+                fs::remove_file(&old)?;
+                removed.push(old);
+            }
+        }
+        Ok((backup_file, removed))
+    }
+
+    /// Backup files in the data directory, newest first. Only names of the
+    /// form `backup_<timestamp>.json` (or `backup_<timestamp>-N.json`) count.
+    pub fn list_backups(&self) -> Result<Vec<PathBuf>, io::Error> {
+        let mut backups: Vec<_> = fs::read_dir(&self.data_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file())
+            .filter_map(|path| {
+                let key = backup_sort_key(path.file_name()?.to_str()?)?;
+                Some((key, path))
+            })
+            .collect();
+        backups.sort_unstable_by_key(|(key, _)| std::cmp::Reverse(*key));
+        Ok(backups.into_iter().map(|(_, path)| path).collect())
+    }
+
+    /// Replaces `data.json` with `backup` and returns its contents. The
+    /// backup is read and validated first, and the current `data.json` is
+    /// itself backed up before being overwritten.
+    pub fn restore_backup(
+        &self,
+        backup: &Path,
+        max_backups: usize,
+    ) -> Result<AppData, io::Error> {
+        let app_data = self.decode(&fs::read(backup)?)?;
+        // Re-encode rather than copy, so the restored file follows the
+        // current encryption setting.
+        let json = serde_json::to_string_pretty(&app_data)?;
+        let contents = self.encode(json.as_bytes())?;
+
+        if self.app_file.exists() {
+            self.backup_data(max_backups)?;
+        }
+        write_atomic(&self.app_file, &contents)?;
+        Ok(app_data)
+    }
+
+    /// Reads notes and todos from another `data.json`-style file.
+    pub fn import_data(&self, path: &Path) -> Result<AppData, io::Error> {
+        self.decode(&fs::read(path)?).map_err(|err| match err.kind() {
+            io::ErrorKind::InvalidData => io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("not a Locus data file ({})", err),
+            ),
+            _ => err,
+        })
+    }
+
+    /// Reads a Markdown file and splits it into `(title, body)` sections
+    /// with `split_markdown_sections`.
+    pub fn import_markdown(&self, path: &Path) -> Result<Vec<(String, String)>, io::Error> {
+        Ok(split_markdown_sections(&fs::read_to_string(path)?))
     }
 
     pub fn export_data(
@@ -82,6 +378,31 @@ impl FileIO {
         }
     }
 
+    /// Writes one note to `dir` as `markdown`, `csv` or `txt`, named after
+    /// its title, and returns the file created.
+    pub fn export_note(&self, note: &Note, format: &str, dir: &Path) -> Result<PathBuf, io::Error> {
+        let item = ExportItem {
+            kind: "Note",
+            title: &note.title,
+            content: &note.content,
+            created_at: &note.created_at,
+            completed: None,
+        };
+        item.write(format, dir)
+    }
+
+    /// Writes one todo to `dir`; see `export_note`.
+    pub fn export_todo(&self, todo: &Todo, format: &str, dir: &Path) -> Result<PathBuf, io::Error> {
+        let item = ExportItem {
+            kind: "Todo",
+            title: &todo.title,
+            content: &todo.content,
+            created_at: &todo.created_at,
+            completed: Some(todo.completed),
+        };
+        item.write(format, dir)
+    }
+
     fn export_json(&self, path: &Path) -> Result<(), io::Error> {
         fs::copy(&self.app_file, path)?;
         Ok(())
@@ -94,10 +415,10 @@ impl FileIO {
         todos: &[Todo],
     ) -> Result<(), io::Error> {
         let mut writer = csv::Writer::from_path(path)?;
-        writer.write_record(&["Type", "Title", "Content", "Created At", "Completed"])?;
+        writer.write_record(["Type", "Title", "Content", "Created At", "Completed"])?;
 
         for note in notes {
-            writer.write_record(&[
+            writer.write_record([
                 "Note",
                 &note.title,
                 &note.content,
@@ -107,7 +428,7 @@ impl FileIO {
         }
 
         for todo in todos {
-            writer.write_record(&[
+            writer.write_record([
                 "Todo",
                 &todo.title,
                 &todo.content,
@@ -143,4 +464,371 @@ impl FileIO {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// The fields a single-item export needs from a note or todo.
+struct ExportItem<'a> {
+    kind: &'a str,
+    title: &'a str,
+    content: &'a str,
+    created_at: &'a str,
+    /// `None` for notes.
+    completed: Option<bool>,
+}
+
+impl ExportItem<'_> {
+    fn write(&self, format: &str, dir: &Path) -> Result<PathBuf, io::Error> {
+        let extension = match format {
+            "markdown" | "md" => "md",
+            "csv" => "csv",
+            "txt" => "txt",
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Unsupported format",
+                ))
+            }
+        };
+        let path = unique_path(dir, &sanitize_filename(self.title), extension);
+
+        match extension {
+            "md" => {
+                let mut file = File::create(&path)?;
+                match self.completed {
+                    Some(done) => {
+                        let status = if done { "✓" } else { "☐" };
+                        writeln!(file, "# {} {}\n", status, self.title)?;
+                    }
+                    None => writeln!(file, "# {}\n", self.title)?,
+                }
+                writeln!(file, "*Created: {}*\n", self.created_at)?;
+                writeln!(file, "{}", self.content)?;
+            }
+            "csv" => {
+                let mut writer = csv::Writer::from_path(&path)?;
+                writer.write_record(["Type", "Title", "Content", "Created At", "Completed"])?;
+                let completed = self.completed.map(|c| c.to_string()).unwrap_or_default();
+                writer.write_record([
+                    self.kind,
+                    self.title,
+                    self.content,
+                    self.created_at,
+                    &completed,
+                ])?;
+                writer.flush()?;
+            }
+            _ => fs::write(&path, format!("{}\n\n{}\n", self.title, self.content))?,
+        }
+        Ok(path)
+    }
+}
+
+/// Orders backup filenames by their timestamp and `-N` suffix, or `None`
+/// for anything that isn't a backup written by `backup_data`.
+fn backup_sort_key(name: &str) -> Option<(chrono::NaiveDateTime, u32)> {
+    let stem = name.strip_prefix("backup_")?.strip_suffix(".json")?;
+    let (timestamp, n) = match stem.split_once('-') {
+        Some((timestamp, n)) => (timestamp, n.parse().ok()?),
+        None => (stem, 1),
+    };
+    let timestamp =
+        chrono::NaiveDateTime::parse_from_str(timestamp, BACKUP_TIMESTAMP_FORMAT).ok()?;
+    Some((timestamp, n))
+}
+
+/// Writes `contents` to a `.tmp` sibling of `path` and renames it into
+/// place, so a crash mid-write leaves the previous file intact.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    let result = File::create(&tmp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(err) = result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(err);
+    }
+    fs::rename(&tmp_path, path)
+}
+
+/// Converts CRLF and lone CR line endings to LF and strips trailing
+/// whitespace from every line, keeping a final newline if there was one.
+pub fn normalize_content(content: &str) -> String {
+    content
+        .replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .split('\n')
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits Markdown text on top-level `#` and `##` headings into
+/// `(heading, body)` pairs. Deeper headings stay in the body, as does
+/// anything inside fenced code blocks; text before the first heading
+/// becomes an "Untitled" section if it isn't blank.
+pub fn split_markdown_sections(text: &str) -> Vec<(String, String)> {
+    let mut sections = Vec::new();
+    let mut title: Option<String> = None;
+    let mut body: Vec<&str> = Vec::new();
+    let mut in_fence = false;
+
+    let mut finish = |title: Option<String>, body: &[&str]| {
+        let content = body.join("\n").trim_matches('\n').trim_end().to_string();
+        match title {
+            Some(title) => sections.push((title, content)),
+            None if !content.trim().is_empty() => {
+                sections.push(("Untitled".to_string(), content))
+            }
+            None => {}
+        }
+    };
+
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let heading = if in_fence {
+            None
+        } else {
+            line.strip_prefix("## ").or_else(|| line.strip_prefix("# "))
+        };
+        match heading {
+            Some(heading) => {
+                finish(title.take(), &body);
+                body.clear();
+                let heading = heading.trim().trim_end_matches('#').trim_end();
+                title = Some(if heading.is_empty() { "Untitled" } else { heading }.to_string());
+            }
+            None => body.push(line),
+        }
+    }
+    finish(title, &body);
+    sections
+}
+
+/// Turns an item title into a safe filename stem: path separators and
+/// characters invalid on common filesystems become `_`, control characters
+/// (including newlines) become spaces, and the result is trimmed, length
+/// limited, and never empty, hidden, or a reserved device name.
+pub fn sanitize_filename(title: &str) -> String {
+    let replaced: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let collapsed = replaced.split_whitespace().collect::<Vec<_>>().join(" ");
+    let truncated: String = collapsed.chars().take(MAX_FILENAME_CHARS).collect();
+    let stem = truncated.trim_matches(|c: char| c == '.' || c.is_whitespace());
+
+    const RESERVED: &[&str] = &["CON", "PRN", "AUX", "NUL", "COM1", "LPT1"];
+    if stem.is_empty() {
+        "untitled".to_string()
+    } else if RESERVED.contains(&stem.to_uppercase().as_str()) {
+        format!("{}_", stem)
+    } else {
+        stem.to_string()
+    }
+}
+
+/// Path for `stem.extension` inside `dir`, adding a `-2`, `-3`, ... suffix
+/// when a file with that name already exists.
+pub fn unique_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    let candidate = dir.join(format!("{}.{}", stem, extension));
+    if !candidate.exists() {
+        return candidate;
+    }
+    (2..)
+        .map(|n| dir.join(format!("{}-{}.{}", stem, n, extension)))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A fresh, empty directory under the system temp dir for one test.
+    pub(crate) fn temp_data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("locus-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn note_with(content: &str) -> Note {
+        let mut note = Note::new("Note".to_string(), "2024-01-01T00:00:00+00:00".to_string());
+        note.content = content.to_string();
+        note
+    }
+
+    #[test]
+    fn sanitize_neutralizes_path_traversal() {
+        assert_eq!(sanitize_filename("../../etc/passwd"), "_.._etc_passwd");
+        assert_eq!(sanitize_filename(".."), "untitled");
+        assert_eq!(sanitize_filename("a/b"), "a_b");
+        assert_eq!(sanitize_filename("a\\b"), "a_b");
+        assert_eq!(sanitize_filename("C:\\Windows"), "C__Windows");
+    }
+
+    #[test]
+    fn sanitize_replaces_nul_and_control_characters() {
+        assert_eq!(sanitize_filename("a\0b"), "a b");
+        assert_eq!(sanitize_filename("line\none\r\ntwo\tthree"), "line one two three");
+        assert_eq!(sanitize_filename("\0\u{7}\u{1b}"), "untitled");
+    }
+
+    #[test]
+    fn sanitize_avoids_reserved_and_hidden_names() {
+        assert_eq!(sanitize_filename("CON"), "CON_");
+        assert_eq!(sanitize_filename("nul"), "nul_");
+        assert_eq!(sanitize_filename("com1"), "com1_");
+        assert_eq!(sanitize_filename(".hidden"), "hidden");
+        assert_eq!(sanitize_filename("   "), "untitled");
+        assert_eq!(sanitize_filename("trailing. "), "trailing");
+    }
+
+    #[test]
+    fn sanitize_limits_length() {
+        let long = "x".repeat(500);
+        assert_eq!(sanitize_filename(&long).chars().count(), MAX_FILENAME_CHARS);
+        let wide = "日".repeat(100);
+        assert_eq!(sanitize_filename(&wide).chars().count(), MAX_FILENAME_CHARS);
+    }
+
+    #[test]
+    fn exports_get_collision_suffixes_inside_the_directory() {
+        let dir = temp_data_dir("export-collisions");
+        let file_io = FileIO::open(dir.clone()).unwrap();
+        let paths: Vec<PathBuf> = ["../escape", "../escape", "..\\escape"]
+            .iter()
+            .map(|title| {
+                let note = Note::new(title.to_string(), "2024-01-01T00:00:00+00:00".to_string());
+                file_io.export_note(&note, "txt", &dir).unwrap()
+            })
+            .collect();
+
+        assert_eq!(paths[0], dir.join("_escape.txt"));
+        assert_eq!(paths[1], dir.join("_escape-2.txt"));
+        assert_eq!(paths[2], dir.join("_escape-3.txt"));
+        assert!(paths.iter().all(|path| path.parent() == Some(dir.as_path()) && path.exists()));
+    }
+
+    #[test]
+    fn failed_write_keeps_the_previous_data() {
+        let dir = temp_data_dir("atomic");
+        let file_io = FileIO::open(dir.clone()).unwrap();
+        file_io.save_data(&[note_with("good")], &[], &[], true).unwrap();
+
+        // A directory where the temp file should go makes the write fail
+        // before `data.json` is touched.
+        fs::create_dir(dir.join("data.json.tmp")).unwrap();
+        assert!(file_io.save_data(&[note_with("bad")], &[], &[], true).is_err());
+
+        let data = file_io.load_data().unwrap();
+        assert_eq!(data.notes.len(), 1);
+        assert_eq!(data.notes[0].content, "good");
+    }
+
+    #[test]
+    fn data_dir_prefers_the_environment_variable() {
+        let home = Some(PathBuf::from("/home/user"));
+        let custom = Some(OsString::from("/srv/locus"));
+        assert_eq!(resolve_data_dir(custom.clone(), home), PathBuf::from("/srv/locus"));
+        assert_eq!(resolve_data_dir(custom, None), PathBuf::from("/srv/locus"));
+    }
+
+    #[test]
+    fn data_dir_falls_back_to_the_home_directory() {
+        let home = Some(PathBuf::from("/home/user"));
+        let expected = PathBuf::from("/home/user/.terminal_notes");
+        assert_eq!(resolve_data_dir(None, home.clone()), expected);
+        // An empty value is ignored.
+        assert_eq!(resolve_data_dir(Some(OsString::new()), home), expected);
+        assert_eq!(resolve_data_dir(None, None), PathBuf::from("./.terminal_notes"));
+    }
+
+    #[test]
+    fn resolved_data_dir_is_created() {
+        let data_dir = temp_data_dir("env").join("custom");
+        let file_io = FileIO::open(resolve_data_dir(Some(data_dir.clone().into()), None)).unwrap();
+        assert_eq!(file_io.data_dir(), data_dir);
+        assert!(data_dir.is_dir());
+        file_io.save_data(&[note_with("here")], &[], &[], true).unwrap();
+        assert!(data_dir.join("data.json").exists());
+    }
+
+    #[test]
+    fn normalize_converts_crlf_and_lone_cr() {
+        assert_eq!(normalize_content("a\r\nb\r\n"), "a\nb\n");
+        assert_eq!(normalize_content("a\rb\rc"), "a\nb\nc");
+        assert_eq!(normalize_content("a\r\nb\rc\nd"), "a\nb\nc\nd");
+    }
+
+    #[test]
+    fn normalize_strips_trailing_spaces_and_tabs() {
+        assert_eq!(normalize_content("a  \nb\t\n  c \t "), "a\nb\n  c");
+        assert_eq!(normalize_content("keep\n"), "keep\n");
+        assert_eq!(normalize_content(""), "");
+    }
+
+    #[test]
+    fn save_normalizes_only_when_asked() {
+        let file_io = FileIO::open(temp_data_dir("normalize")).unwrap();
+        let notes = [note_with("a \r\nb\t\r")];
+
+        file_io.save_data(&notes, &[], &[], true).unwrap();
+        assert_eq!(file_io.load_data().unwrap().notes[0].content, "a\nb\n");
+
+        file_io.save_data(&notes, &[], &[], false).unwrap();
+        assert_eq!(file_io.load_data().unwrap().notes[0].content, "a \r\nb\t\r");
+    }
+
+    #[test]
+    fn legacy_notes_get_unique_ids_and_links_resolve() {
+        let dir = temp_data_dir("legacy-ids");
+        let note = |title: &str| {
+            format!(
+                r#"{{"title":"{}","content":"","tags":[],
+                    "created_at":"2024-01-01T00:00:00+00:00"}}"#,
+                title
+            )
+        };
+        let todo = r#"{"title":"T","content":"","created_at":"2024-01-02T00:00:00+00:00",
+            "completed":false,"tags":[],"due_date":null,"severity":"Medium",
+            "source_note":"2024-01-01T00:00:00+00:00"}"#;
+        let json = format!(r#"{{"notes":[{},{}],"todos":[{}]}}"#, note("A"), note("B"), todo);
+        std::fs::write(dir.join("data.json"), json).unwrap();
+
+        let file_io = FileIO::open(dir.clone()).unwrap();
+        let data = file_io.load_data().unwrap();
+        let (a, b) = (data.notes[0].id, data.notes[1].id);
+        assert!(a != 0 && b != 0 && a != b);
+        assert_eq!(data.todos[0].source_note_id, Some(a));
+        assert_eq!(data.todos[0].source_note, None);
+
+        file_io.save_data(&data.notes, &data.todos, &[], false).unwrap();
+        let saved = std::fs::read_to_string(dir.join("data.json")).unwrap();
+        assert!(!saved.contains("\"source_note\""));
+        let reloaded = file_io.load_data().unwrap();
+        assert_eq!((reloaded.notes[0].id, reloaded.notes[1].id), (a, b));
+        assert_eq!(reloaded.todos[0].source_note_id, Some(a));
+    }
+
+    #[test]
+    fn duplicate_note_ids_are_reassigned() {
+        let mut notes = vec![note_with("a"), note_with("b"), note_with("c")];
+        notes.iter_mut().for_each(|n| n.id = 7);
+        let data = AppData { notes, ..AppData::default() };
+        let data = data.backfill();
+        let ids: HashSet<u64> = data.notes.iter().map(|n| n.id).collect();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(data.notes[0].id, 7);
+    }
+}
@@ -0,0 +1,144 @@
+// src/keymap.rs
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::HashMap;
+
+/// Something a single key press can do outside of text entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    CommandMode,
+    NextItem,
+    PreviousItem,
+    LastItem,
+    OpenItem,
+    ToggleComplete,
+    PickSeverity,
+    PickDueDate,
+    FollowLink,
+    CopyTitle,
+    Undo,
+    Goto,
+    ScrollLeft,
+    ScrollRight,
+    QuickNote,
+    RaiseSeverity,
+    LowerSeverity,
+    NarrowList,
+    WidenList,
+}
+
+impl Action {
+    /// Every action with its name in the `keymap` config and default keys.
+    const DEFAULTS: [(Action, &'static str, &'static [&'static str]); 20] = [
+        (Action::Quit, "quit", &["Ctrl+q"]),
+        (Action::CommandMode, "command_mode", &[":"]),
+        (Action::NextItem, "next_item", &["j", "Down"]),
+        (Action::PreviousItem, "previous_item", &["k", "Up"]),
+        (Action::LastItem, "last_item", &["G"]),
+        (Action::OpenItem, "open_item", &["Enter"]),
+        (Action::ToggleComplete, "toggle_complete", &["Space"]),
+        (Action::PickSeverity, "pick_severity", &["s"]),
+        (Action::PickDueDate, "pick_due_date", &["D"]),
+        (Action::FollowLink, "follow_link", &["f"]),
+        (Action::CopyTitle, "copy_title", &["Y"]),
+        (Action::Undo, "undo", &["u"]),
+        (Action::Goto, "goto", &["Ctrl+p"]),
+        (Action::ScrollLeft, "scroll_left", &["h", "Left"]),
+        (Action::ScrollRight, "scroll_right", &["l", "Right"]),
+        (Action::QuickNote, "quick_note", &["Ctrl+n"]),
+        (Action::RaiseSeverity, "raise_severity", &["+"]),
+        (Action::LowerSeverity, "lower_severity", &["-"]),
+        (Action::NarrowList, "narrow_list", &["Ctrl+h"]),
+        (Action::WidenList, "widen_list", &["Ctrl+l"]),
+    ];
+}
+
+/// Maps key presses to actions: the defaults, with any action named in the
+/// config's `keymap` rebound to the keys listed there.
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Action>,
+}
+
+impl Keymap {
+    pub fn new(overrides: &HashMap<String, Vec<String>>) -> Self {
+        for name in overrides.keys() {
+            if !Action::DEFAULTS.iter().any(|(_, known, _)| known == name) {
+                log::warn!("ignoring keymap entry for unknown action '{}'", name);
+            }
+        }
+
+        let mut bindings = HashMap::new();
+        for (action, name, defaults) in Action::DEFAULTS {
+            let keys: Vec<&str> = match overrides.get(name) {
+                Some(keys) => keys.iter().map(String::as_str).collect(),
+                None => defaults.to_vec(),
+            };
+            for spec in keys {
+                match parse_key(spec) {
+                    Some(key) => {
+                        bindings.insert(key, action);
+                    }
+                    None => log::warn!("ignoring unknown key '{}' for {}", spec, name),
+                }
+            }
+        }
+        Keymap { bindings }
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&normalize(key.code, key.modifiers)).copied()
+    }
+}
+
+/// Shift is already part of the character for `Char` keys, and terminals
+/// disagree on whether they report it, so only Ctrl and Alt are compared.
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    let modifiers = match code {
+        KeyCode::Char(_) => modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT),
+        _ => modifiers,
+    };
+    (code, modifiers)
+}
+
+/// Parses a key such as "j", "G", "Down", "Space" or "Ctrl+q".
+fn parse_key(spec: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+    loop {
+        if let Some(key) = rest.strip_prefix("Ctrl+") {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = key;
+        } else if let Some(key) = rest.strip_prefix("Alt+") {
+            modifiers |= KeyModifiers::ALT;
+            rest = key;
+        } else if let Some(key) = rest.strip_prefix("Shift+") {
+            modifiers |= KeyModifiers::SHIFT;
+            rest = key;
+        } else {
+            break;
+        }
+    }
+
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest {
+            "Space" => KeyCode::Char(' '),
+            "Enter" => KeyCode::Enter,
+            "Esc" => KeyCode::Esc,
+            "Tab" => KeyCode::Tab,
+            "Backspace" => KeyCode::Backspace,
+            "Delete" => KeyCode::Delete,
+            "Up" => KeyCode::Up,
+            "Down" => KeyCode::Down,
+            "Left" => KeyCode::Left,
+            "Right" => KeyCode::Right,
+            "Home" => KeyCode::Home,
+            "End" => KeyCode::End,
+            "PageUp" => KeyCode::PageUp,
+            "PageDown" => KeyCode::PageDown,
+            _ => KeyCode::F(rest.strip_prefix('F')?.parse().ok()?),
+        },
+    };
+    Some(normalize(code, modifiers))
+}
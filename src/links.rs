@@ -0,0 +1,52 @@
+// src/links.rs
+use crate::note::Note;
+use std::ops::Range;
+
+/// Byte ranges of the `[[title]]` links in `content`, brackets included.
+/// Links don't span lines and an empty `[[]]` isn't one.
+pub fn find_links(content: &str) -> Vec<Range<usize>> {
+    let mut links = Vec::new();
+    let mut from = 0;
+    while let Some(open) = content[from..].find("[[").map(|i| from + i) {
+        let Some(close) = content[open + 2..].find("]]").map(|i| open + 2 + i) else {
+            break;
+        };
+        let target = &content[open + 2..close];
+        if target.contains('\n') {
+            // Try again from the line break in case a link starts after it.
+            from = open + 2 + target.find('\n').unwrap_or(0);
+            continue;
+        }
+        if !target.trim().is_empty() {
+            links.push(open..close + 2);
+        }
+        from = close + 2;
+    }
+    links
+}
+
+/// The title a link points to: the text between the brackets, trimmed.
+pub fn link_target(content: &str, link: &Range<usize>) -> String {
+    content[link.start + 2..link.end - 2].trim().to_string()
+}
+
+/// The link to follow from `cursor`: the one the cursor is inside, or else
+/// the first link in the content.
+pub fn link_near(content: &str, cursor: usize) -> Option<String> {
+    let links = find_links(content);
+    let link = links
+        .iter()
+        .find(|link| link.contains(&cursor))
+        .or_else(|| links.first())?;
+    Some(link_target(content, link))
+}
+
+/// Index of the live note titled `target`, preferring an exact match over
+/// one that only differs in case.
+pub fn resolve(target: &str, notes: &[Note]) -> Option<usize> {
+    let live = || notes.iter().enumerate().filter(|(_, n)| !n.archived);
+    live()
+        .find(|(_, n)| n.title == target)
+        .or_else(|| live().find(|(_, n)| n.title.to_lowercase() == target.to_lowercase()))
+        .map(|(i, _)| i)
+}
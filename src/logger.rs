@@ -0,0 +1,70 @@
+// src/logger.rs
+use chrono::Local;
+use log::{LevelFilter, Metadata, Record};
+use std::{
+    env,
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+/// Appends timestamped records to `<data_dir>/locus.log`.
+struct FileLogger {
+    level: LevelFilter,
+    file: Mutex<File>,
+}
+
+impl log::Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Ok(mut file) = self.file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} {}",
+                Local::now().to_rfc3339(),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Enables file logging when `LOCUS_LOG` names a level (`error`, `warn`,
+/// `info`, `debug`, `trace`). Logging stays off when the variable is unset
+/// or the log file can't be opened.
+pub fn init(data_dir: &Path) {
+    let Some(level) = env::var("LOCUS_LOG")
+        .ok()
+        .and_then(|value| value.parse::<LevelFilter>().ok())
+    else {
+        return;
+    };
+    let Ok(file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.join("locus.log"))
+    else {
+        return;
+    };
+
+    let logger = FileLogger {
+        level,
+        file: Mutex::new(file),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -1,22 +1,58 @@
 // src/main.rs
 mod app;
+mod browser;
+mod catalog;
+mod clipboard;
+mod commands;
+mod config;
+mod crypto;
+mod dates;
+mod editor;
 mod file_io;
+mod keymap;
+mod links;
+mod logger;
+mod markdown;
 mod note;
+mod search;
+mod sort;
+mod tags;
+mod theme;
 mod todo;
 mod ui;
+mod undo;
 
 use crossterm::{
     event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
+use std::io::{self, BufRead, IsTerminal, Read};
 use std::time::Duration;
 
 use app::{App, AppMode, AppSection};
+use keymap::Action;
 
 fn main() -> Result<(), io::Error> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--script") {
+        return run_script();
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--export") {
+        if let Err(err) = export(&args[at + 1..]) {
+            eprintln!("Export failed: {}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--add-note") {
+        let title = args.get(at + 1).filter(|arg| !arg.starts_with("--"));
+        return add_note(title.map(String::as_str));
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -39,9 +75,15 @@ fn main() -> Result<(), io::Error> {
     };
 
     let res = run_app(&mut terminal, &mut app);
+    if !app.locked {
+        app.save_session();
+    }
 
-    if app.has_unsaved_changes() {
+    // A clean exit has already saved or discarded through the quit prompt;
+    // this only rescues changes when the event loop itself failed.
+    if res.is_err() && app.has_unsaved_changes() {
         if let Err(save_err) = app.save_data() {
+            log::error!("save on exit failed: {}", save_err);
             eprintln!("Error saving data on exit: {}", save_err);
         }
     }
@@ -55,20 +97,140 @@ fn main() -> Result<(), io::Error> {
     terminal.show_cursor()?;
 
     if let Err(err) = res {
+        log::error!("event loop failed: {:?}", err);
         eprintln!("An error occurred: {:?}", err);
     }
 
     Ok(())
 }
 
+/// Executes commands read line by line from stdin against `App` without
+/// touching the terminal, printing each resulting status message.
+fn run_script() -> Result<(), io::Error> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--script reads commands from piped stdin",
+        ));
+    }
+
+    let mut app = App::new()?;
+    if app.locked {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "data.json is encrypted; --script can't prompt for the passphrase",
+        ));
+    }
+    // Startup warnings, such as a corrupt data file being set aside.
+    if let Some(message) = app.status_message.take() {
+        eprintln!("{}", message);
+    }
+    for line in stdin.lock().lines() {
+        let line = line?;
+        let command = line.trim().trim_start_matches(':');
+        if command.is_empty() {
+            continue;
+        }
+
+        app.command_buffer = command.to_string();
+        app.execute_command();
+        // There is nobody to answer the quit prompt.
+        if let AppMode::QuitPrompt = app.mode {
+            app.cancel_quit();
+            app.status_message =
+                Some("Unsaved changes! Use save first or q! to force quit".to_string());
+        }
+        if let Some(message) = app.status_message.take() {
+            println!("{}", message);
+        }
+        if app.should_quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// `--export <markdown|csv> [--out <path>] [--all]`: writes the same file
+/// `:export-md`/`:export-csv` would and exits, never touching the terminal.
+fn export(args: &[String]) -> Result<(), io::Error> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidInput, message.to_string());
+    let format = match args.first().map(String::as_str) {
+        Some("markdown" | "md") => "markdown",
+        Some("csv") => "csv",
+        _ => return Err(invalid("--export needs a format: markdown or csv")),
+    };
+    let mut path = app::default_export_path(format);
+    let mut all = false;
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--out" => match rest.next() {
+                Some(out) => path = out.into(),
+                None => return Err(invalid("--out needs a path")),
+            },
+            "--all" => all = true,
+            _ => return Err(invalid(&format!("unexpected argument '{}' for --export", arg))),
+        }
+    }
+
+    let app = App::new()?;
+    if app.locked {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "data.json is encrypted; --export can't prompt for the passphrase",
+        ));
+    }
+    let (notes, todos) = app.export_data(format, &path, all)?;
+    log::info!("exported {} notes, {} todos as {} to {:?}", notes, todos, format, path);
+    println!("Exported {} notes and {} todos to: {}", notes, todos, path.display());
+    Ok(())
+}
+
+/// `--add-note [title]`: saves piped stdin as a new note and exits.
+fn add_note(title: Option<&str>) -> Result<(), io::Error> {
+    let mut stdin = io::stdin();
+    if stdin.is_terminal() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--add-note reads the note's text from piped stdin",
+        ));
+    }
+    let mut content = String::new();
+    stdin.read_to_string(&mut content)?;
+
+    let mut app = App::new()?;
+    if app.locked {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "data.json is encrypted; --add-note can't prompt for the passphrase",
+        ));
+    }
+    let title = app.add_note(title, content.trim_end_matches(['\n', '\r']).to_string());
+    app.save_data()?;
+    println!("Added note '{}'", title);
+    Ok(())
+}
+
 fn run_app<B: ratatui::backend::Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
 ) -> io::Result<()> {
     let tick_rate = Duration::from_millis(100);
     let mut last_tick = std::time::Instant::now();
+    let mut window_title = String::new();
 
     loop {
+        if app.should_quit {
+            break;
+        }
+
+        let breadcrumb = app.breadcrumb();
+        if breadcrumb != window_title {
+            execute!(io::stdout(), SetTitle(&breadcrumb))?;
+            window_title = breadcrumb;
+        }
+
         terminal.draw(|f| ui::draw(f, app))?;
 
         let timeout = tick_rate
@@ -76,13 +238,37 @@ fn run_app<B: ratatui::backend::Backend>(
             .unwrap_or_else(|| Duration::from_secs(0));
 
         if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
-                    if !app.has_unsaved_changes() {
-                        break;
-                    }
-                    app.status_message =
-                        Some("Unsaved changes! Use :save or :q! to force quit".to_string());
+            let event = event::read()?;
+            // Resizing clears the screen too, so the next draw starts from
+            // scratch instead of diffing against the old layout.
+            if let Event::Resize(width, height) = event {
+                terminal.resize(Rect::new(0, 0, width, height))?;
+            }
+            if let Event::Key(key) = event {
+                // While typing, only a Ctrl/Alt chord bound to quit is honored.
+                let typing = matches!(
+                    app.mode,
+                    AppMode::Editing
+                        | AppMode::Command
+                        | AppMode::Renaming
+                        | AppMode::Goto
+                        | AppMode::Password
+                ) && !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+                // Quick capture works wherever no popup or text field has
+                // the keyboard.
+                let browsing = matches!(
+                    app.mode,
+                    AppMode::MainMenu
+                        | AppMode::Normal
+                        | AppMode::Help
+                        | AppMode::Inspect
+                        | AppMode::Search
+                );
+                let action = app.keymap.action(&key);
+                if !typing && action == Some(Action::Quit) {
+                    app.request_quit(false);
+                } else if browsing && action == Some(Action::QuickNote) {
+                    app.create_new_note();
                 } else {
                     match app.mode {
                         AppMode::MainMenu => handle_main_menu_input(app, key),
@@ -91,8 +277,18 @@ fn run_app<B: ratatui::backend::Backend>(
                         AppMode::Editing => handle_editing_mode_input(app, key),
                         AppMode::Help => handle_help_mode_input(app, key),
                         AppMode::Renaming => handle_renaming_mode_input(app, key),
+                        AppMode::Confirm => handle_confirm_mode_input(app, key),
+                        AppMode::SeverityPicker => handle_severity_picker_input(app, key),
+                        AppMode::Inspect => handle_inspect_mode_input(app, key),
+                        AppMode::Search => handle_search_mode_input(app, key),
+                        AppMode::Restore => handle_restore_mode_input(app, key),
+                        AppMode::Goto => handle_goto_mode_input(app, key),
+                        AppMode::Password => handle_password_mode_input(app, key),
+                        AppMode::QuitPrompt => handle_quit_prompt_input(app, key),
+                        AppMode::DatePicker => handle_date_picker_input(app, key),
                     }
                 }
+                app.mark_edited();
             }
         }
 
@@ -105,77 +301,135 @@ fn run_app<B: ratatui::backend::Backend>(
 }
 
 fn handle_main_menu_input(app: &mut App, key: event::KeyEvent) {
-    match key.code {
-        KeyCode::Char(':') => app.mode = AppMode::Command,
-        KeyCode::Char('j') | KeyCode::Down => app.next_menu_item(),
-        KeyCode::Char('k') | KeyCode::Up => app.previous_menu_item(),
-        KeyCode::Enter => app.select_menu_item(),
+    match app.keymap.action(&key) {
+        Some(Action::CommandMode) => app.mode = AppMode::Command,
+        Some(Action::NextItem) => app.next_menu_item(),
+        Some(Action::PreviousItem) => app.previous_menu_item(),
+        Some(Action::OpenItem) => app.select_menu_item(),
         _ => {}
     }
 }
 
 fn handle_normal_mode_input(app: &mut App, key: event::KeyEvent) {
+    // `dd` and `gg` are fixed two-key sequences outside the keymap.
+    let pending = app.pending_key.take();
     match key.code {
-        KeyCode::Char(':') => app.mode = AppMode::Command,
-        KeyCode::Char('j') | KeyCode::Down => match app.section {
+        KeyCode::Char('d') if pending == Some('d') => return app.delete_current_line(),
+        KeyCode::Char('g') if pending == Some('g') => return app.jump_to_edge(false),
+        _ => {}
+    }
+
+    let Some(action) = app.keymap.action(&key) else {
+        if let KeyCode::Char(c @ ('d' | 'g')) = key.code {
+            app.pending_key = Some(c);
+        }
+        return;
+    };
+    match action {
+        Action::CommandMode => app.mode = AppMode::Command,
+        Action::NextItem => match app.section {
             AppSection::Notes => app.next_note(),
             AppSection::Todos => app.next_todo(),
+            AppSection::Catalog => app.next_catalog(),
             _ => {}
         },
-        KeyCode::Char('k') | KeyCode::Up => match app.section {
+        Action::PreviousItem => match app.section {
             AppSection::Notes => app.previous_note(),
             AppSection::Todos => app.previous_todo(),
+            AppSection::Catalog => app.previous_catalog(),
             _ => {}
         },
-        KeyCode::Enter => match app.section {
-            AppSection::Notes => {
-                if let Some(idx) = app.selected_note {
-                    app.current_note = Some(idx);
-                    app.mode = AppMode::Editing;
-                }
-            }
-            AppSection::Todos => {
-                if let Some(idx) = app.selected_todo {
-                    app.current_todo = Some(idx);
-                    app.mode = AppMode::Editing;
-                }
-            }
-            _ => {}
-        },
-        KeyCode::Char(' ') if matches!(app.section, AppSection::Todos) => {
+        Action::LastItem => app.jump_to_edge(true),
+        Action::OpenItem => app.open_selected_item(),
+        Action::ToggleComplete if matches!(app.section, AppSection::Todos) => {
             app.toggle_todo_completion()
         }
+        Action::PickSeverity if matches!(app.section, AppSection::Todos) => {
+            app.open_severity_picker()
+        }
+        Action::RaiseSeverity if matches!(app.section, AppSection::Todos) => {
+            app.step_severity(true)
+        }
+        Action::LowerSeverity if matches!(app.section, AppSection::Todos) => {
+            app.step_severity(false)
+        }
+        Action::PickDueDate if matches!(app.section, AppSection::Todos) => {
+            app.open_date_picker()
+        }
+        Action::FollowLink => app.follow_link(),
+        Action::NarrowList => app.resize_list(false),
+        Action::WidenList => app.resize_list(true),
+        Action::CopyTitle => app.copy_selected_title(),
+        Action::Undo => app.undo_structural(),
+        Action::Goto => app.open_goto(),
+        Action::ScrollLeft => app.scroll_editor(false),
+        Action::ScrollRight => app.scroll_editor(true),
         _ => {}
     }
 }
 
 fn handle_command_mode_input(app: &mut App, key: event::KeyEvent) {
     match key.code {
-        KeyCode::Esc => app.mode = AppMode::Normal,
+        KeyCode::Esc => app.leave_command_mode(),
         KeyCode::Enter => {
             if app
                 .status_message
                 .as_deref()
-                .map_or(false, |msg| msg == "Enter new name:")
+                .is_some_and(|msg| msg == "Enter new name:")
             {
                 let new_name = app.command_buffer.clone();
                 app.finish_rename(new_name);
             } else {
+                app.record_command();
                 app.execute_command();
             }
         }
-        KeyCode::Char(c) => app.command_buffer.push(c),
+        KeyCode::Up => app.history_previous(),
+        KeyCode::Down => app.history_next(),
+        KeyCode::Tab => app.complete_command(),
+        KeyCode::Char(c) => {
+            app.reset_command_recall();
+            app.command_buffer.push(c);
+            app.preview_command_matches();
+        }
         KeyCode::Backspace => {
+            app.reset_command_recall();
             app.command_buffer.pop();
+            app.preview_command_matches();
         }
         _ => {}
     }
 }
 
 fn handle_editing_mode_input(app: &mut App, key: event::KeyEvent) {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        match key.code {
+            KeyCode::Char('z') => app.undo_edit(),
+            KeyCode::Char('y') | KeyCode::Char('r') => app.redo_edit(),
+            KeyCode::Char('w') => app.delete_word(),
+            KeyCode::Char('v') => app.paste_clipboard(),
+            KeyCode::Char('c') => app.copy_current_content(),
+            KeyCode::Char('d') => app.insert_timestamp(false),
+            KeyCode::Char('t') => app.insert_timestamp(true),
+            KeyCode::Char('o') => app.enter_command_from_editing(),
+            KeyCode::Left => app.cursor_word_left(),
+            KeyCode::Right => app.cursor_word_right(),
+            _ => {}
+        }
+        return;
+    }
+
     match key.code {
-        KeyCode::Esc => app.mode = AppMode::Normal,
+        KeyCode::Esc => app.stop_editing(),
         KeyCode::Enter => app.insert_new_line(),
+        KeyCode::Tab => app.insert_tab(),
+        KeyCode::BackTab => app.dedent_line(),
+        KeyCode::Left => app.cursor_left(),
+        KeyCode::Right => app.cursor_right(),
+        KeyCode::Up => app.cursor_up(),
+        KeyCode::Down => app.cursor_down(),
+        KeyCode::Home => app.cursor_line_start(),
+        KeyCode::End => app.cursor_line_end(),
         KeyCode::Char(c) => app.insert_char(c),
         KeyCode::Backspace => app.delete_char(),
         _ => {}
@@ -204,4 +458,113 @@ fn handle_renaming_mode_input(app: &mut App, key: event::KeyEvent) {
         }
         _ => {}
     }
-}
\ No newline at end of file
+}
+
+fn handle_confirm_mode_input(app: &mut App, key: event::KeyEvent) {
+    match key.code {
+        KeyCode::Char('y') | KeyCode::Char('Y') => app.confirm_pending_action(),
+        KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => app.cancel_pending_action(),
+        _ => {}
+    }
+}
+
+fn handle_quit_prompt_input(app: &mut App, key: event::KeyEvent) {
+    match key.code {
+        KeyCode::Char('s') | KeyCode::Char('S') => app.save_and_quit(),
+        KeyCode::Char('d') | KeyCode::Char('D') => app.request_quit(true),
+        KeyCode::Char('c') | KeyCode::Char('C') | KeyCode::Esc => app.cancel_quit(),
+        _ => {}
+    }
+}
+
+fn handle_date_picker_input(app: &mut App, key: event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.mode = AppMode::Normal,
+        KeyCode::PageUp => app.move_date_choice_month(false),
+        KeyCode::PageDown => app.move_date_choice_month(true),
+        _ => match app.keymap.action(&key) {
+            Some(Action::ScrollLeft) => app.move_date_choice(-1),
+            Some(Action::ScrollRight) => app.move_date_choice(1),
+            Some(Action::PreviousItem) => app.move_date_choice(-7),
+            Some(Action::NextItem) => app.move_date_choice(7),
+            Some(Action::OpenItem) => app.apply_date_choice(),
+            _ => {}
+        },
+    }
+}
+
+fn handle_severity_picker_input(app: &mut App, key: event::KeyEvent) {
+    if key.code == KeyCode::Esc {
+        app.mode = AppMode::Normal;
+        return;
+    }
+    match app.keymap.action(&key) {
+        Some(Action::NextItem) => app.next_severity_choice(),
+        Some(Action::PreviousItem) => app.previous_severity_choice(),
+        Some(Action::OpenItem) => app.apply_severity_choice(),
+        _ => {}
+    }
+}
+
+fn handle_inspect_mode_input(app: &mut App, key: event::KeyEvent) {
+    if key.code == KeyCode::Esc {
+        app.mode = AppMode::Normal;
+        return;
+    }
+    match app.keymap.action(&key) {
+        Some(Action::NextItem) => app.scroll_inspect(true),
+        Some(Action::PreviousItem) => app.scroll_inspect(false),
+        _ => {}
+    }
+}
+
+fn handle_search_mode_input(app: &mut App, key: event::KeyEvent) {
+    if key.code == KeyCode::Esc {
+        app.mode = AppMode::Normal;
+        return;
+    }
+    match app.keymap.action(&key) {
+        Some(Action::CommandMode) => app.mode = AppMode::Command,
+        Some(Action::NextItem) => app.next_search_result(),
+        Some(Action::PreviousItem) => app.previous_search_result(),
+        Some(Action::OpenItem) => app.open_search_result(),
+        _ => {}
+    }
+}
+
+fn handle_restore_mode_input(app: &mut App, key: event::KeyEvent) {
+    if key.code == KeyCode::Esc {
+        app.mode = AppMode::Normal;
+        return;
+    }
+    match app.keymap.action(&key) {
+        Some(Action::NextItem) => app.next_backup(),
+        Some(Action::PreviousItem) => app.previous_backup(),
+        Some(Action::OpenItem) => app.restore_selected_backup(),
+        _ => {}
+    }
+}
+
+fn handle_goto_mode_input(app: &mut App, key: event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.mode = AppMode::Normal,
+        KeyCode::Enter => app.open_goto_match(),
+        KeyCode::Down => app.next_goto_match(),
+        KeyCode::Up => app.previous_goto_match(),
+        KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => app.goto_push(c),
+        KeyCode::Backspace => app.goto_pop(),
+        _ => {}
+    }
+}
+
+fn handle_password_mode_input(app: &mut App, key: event::KeyEvent) {
+    match key.code {
+        KeyCode::Esc => app.cancel_password(),
+        KeyCode::Enter => app.submit_password(),
+        KeyCode::Char(c) => app.password_buffer.push(c),
+        KeyCode::Backspace => {
+            app.password_buffer.pop();
+        }
+        _ => {}
+    }
+}
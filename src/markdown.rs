@@ -0,0 +1,82 @@
+// src/markdown.rs
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+
+/// Renders a small subset of Markdown for the note preview: `#` headings,
+/// `**bold**`, `*italic*`, and `-`/`*`/`+`/numbered list items. Anything
+/// else is shown as written. Headings are drawn in `heading_color`.
+pub fn render(content: &str, heading_color: Color) -> Vec<Line<'static>> {
+    content.lines().map(|line| render_line(line, heading_color)).collect()
+}
+
+fn render_line(line: &str, heading_color: Color) -> Line<'static> {
+    let trimmed = line.trim_start();
+    let indent = line.len() - trimmed.len();
+
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if (1..=6).contains(&level) {
+        if let Some(text) = trimmed[level..].strip_prefix(' ') {
+            let mut style = Style::default().fg(heading_color).add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            return Line::from(Span::styled(text.trim().to_string(), style));
+        }
+    }
+
+    // Nested items are indented two columns per level of source indent.
+    let pad = " ".repeat(indent / 2 * 2);
+    if let Some(item) = ["- ", "* ", "+ "].iter().find_map(|m| trimmed.strip_prefix(m)) {
+        let mut spans = vec![Span::raw(format!("{}  • ", pad))];
+        spans.extend(inline_spans(item));
+        return Line::from(spans);
+    }
+    if let Some((number, item)) = trimmed.split_once(". ") {
+        if !number.is_empty() && number.chars().all(|c| c.is_ascii_digit()) {
+            let mut spans = vec![Span::raw(format!("{}{:>3}. ", pad, number))];
+            spans.extend(inline_spans(item));
+            return Line::from(spans);
+        }
+    }
+
+    Line::from(inline_spans(line))
+}
+
+/// Splits `text` into plain, bold and italic spans. Markers without a
+/// matching closer are kept as literal text.
+fn inline_spans(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '*' {
+            let (marker, style) = if rest.starts_with("**") {
+                ("**", Style::default().add_modifier(Modifier::BOLD))
+            } else {
+                ("*", Style::default().add_modifier(Modifier::ITALIC))
+            };
+            let inner = &rest[marker.len()..];
+            if let Some(end) = inner.find(marker).filter(|&end| end > 0) {
+                if !plain.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut plain)));
+                }
+                spans.push(Span::styled(inner[..end].to_string(), style));
+                rest = &inner[end + marker.len()..];
+                continue;
+            }
+            plain.push_str(marker);
+            rest = inner;
+            continue;
+        }
+        plain.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span::raw(plain));
+    }
+    spans
+}
@@ -1,31 +1,207 @@
 // src/note.rs
+use crate::tags::normalize_tag;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Note {
+    /// Stable identity that survives renames and edits; todos link to
+    /// notes by it. `0` until assigned, so older files get one on load.
+    #[serde(default)]
+    pub id: u64,
     pub title: String,
     pub content: String,
     pub created_at: String,
+    /// RFC3339 time of the last edit or rename. Older files lack it and
+    /// get `created_at` on load.
+    #[serde(default)]
+    pub updated_at: String,
     pub tags: Vec<String>,
+    /// Pinned notes are listed ahead of all others.
+    #[serde(default)]
+    pub pinned: bool,
+    /// Archived notes are hidden from the list until `:unarchive`d.
+    #[serde(default)]
+    pub archived: bool,
+    /// Soft-wrap long lines in the editor; off for code, which then
+    /// scrolls horizontally instead.
+    #[serde(default = "default_wrap")]
+    pub wrap: bool,
+    /// Target word count set with `:goal`; the editor header shows progress.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub word_goal: Option<usize>,
+}
+
+fn default_wrap() -> bool {
+    true
 }
 
 impl Note {
     pub fn new(title: String, created_at: String) -> Self {
         Note {
+            id: 0,
             title,
             content: String::new(),
+            updated_at: created_at.clone(),
             created_at,
             tags: Vec::new(),
+            pinned: false,
+            archived: false,
+            wrap: true,
+            word_goal: None,
         }
     }
 
-    pub fn add_tag(&mut self, tag: String) {
+    pub fn add_tag(&mut self, tag: &str) -> Result<(), String> {
+        let tag = normalize_tag(tag)?;
         if !self.tags.contains(&tag) {
             self.tags.push(tag);
         }
+        Ok(())
     }
 
     pub fn remove_tag(&mut self, tag: &str) {
         self.tags.retain(|t| t != tag);
     }
-}
\ No newline at end of file
+
+    pub fn word_count(&self) -> usize {
+        self.content.split_whitespace().count()
+    }
+
+    /// Returns `(checked, total)` for `- [ ]`/`- [x]` checklist lines, or
+    /// `None` when the note has no checklist items.
+    pub fn checklist_progress(&self) -> Option<(usize, usize)> {
+        let mut checked = 0;
+        let mut total = 0;
+        for line in self.content.lines() {
+            let line = line.trim_start();
+            let item = line
+                .strip_prefix("- ")
+                .or_else(|| line.strip_prefix("* "));
+            match item.map(|rest| rest.get(..3)) {
+                Some(Some("[ ]")) => total += 1,
+                Some(Some("[x]")) | Some(Some("[X]")) => {
+                    checked += 1;
+                    total += 1;
+                }
+                _ => {}
+            }
+        }
+        if total == 0 {
+            None
+        } else {
+            Some((checked, total))
+        }
+    }
+
+    /// Rewraps paragraphs to `width` columns, leaving blank lines, lists,
+    /// headings, indented lines and fenced code blocks untouched. A width of
+    /// 0 joins each paragraph onto a single line. Returns whether the content
+    /// changed.
+    pub fn reflow(&mut self, width: usize) -> bool {
+        let mut output: Vec<String> = Vec::new();
+        let mut paragraph: Vec<&str> = Vec::new();
+        let mut in_code_block = false;
+
+        for line in self.content.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") {
+                flush_paragraph(&mut paragraph, width, &mut output);
+                in_code_block = !in_code_block;
+                output.push(line.to_string());
+            } else if in_code_block || trimmed.is_empty() || is_structural_line(line) {
+                flush_paragraph(&mut paragraph, width, &mut output);
+                output.push(line.to_string());
+            } else {
+                paragraph.push(line);
+            }
+        }
+        flush_paragraph(&mut paragraph, width, &mut output);
+
+        let mut reflowed = output.join("\n");
+        if self.content.ends_with('\n') {
+            reflowed.push('\n');
+        }
+        if reflowed == self.content {
+            return false;
+        }
+        self.content = reflowed;
+        true
+    }
+
+    /// Strips trailing whitespace from every line and collapses runs of three
+    /// or more blank lines into one. Returns whether the content changed.
+    pub fn trim_whitespace(&mut self) -> bool {
+        let mut output: Vec<&str> = Vec::new();
+        let mut blank_run = 0;
+        for line in self.content.lines().map(str::trim_end) {
+            if line.is_empty() {
+                blank_run += 1;
+            } else {
+                flush_blank_run(blank_run, &mut output);
+                blank_run = 0;
+                output.push(line);
+            }
+        }
+        flush_blank_run(blank_run, &mut output);
+
+        let mut trimmed = output.join("\n");
+        if self.content.ends_with('\n') {
+            trimmed.push('\n');
+        }
+        if trimmed == self.content {
+            return false;
+        }
+        self.content = trimmed;
+        true
+    }
+}
+
+fn flush_blank_run(blank_run: usize, output: &mut Vec<&str>) {
+    let kept = if blank_run >= 3 { 1 } else { blank_run };
+    output.extend(std::iter::repeat_n("", kept));
+}
+
+fn is_structural_line(line: &str) -> bool {
+    if line.starts_with("    ") || line.starts_with('\t') {
+        return true;
+    }
+    let trimmed = line.trim_start();
+    if ["- ", "* ", "+ ", "#", ">"]
+        .iter()
+        .any(|prefix| trimmed.starts_with(prefix))
+    {
+        return true;
+    }
+    let digits = trimmed.chars().take_while(|c| c.is_ascii_digit()).count();
+    digits > 0 && trimmed[digits..].starts_with(". ")
+}
+
+fn flush_paragraph(paragraph: &mut Vec<&str>, width: usize, output: &mut Vec<String>) {
+    if paragraph.is_empty() {
+        return;
+    }
+    let words: Vec<&str> = paragraph
+        .iter()
+        .flat_map(|line| line.split_whitespace())
+        .collect();
+    paragraph.clear();
+
+    if width == 0 {
+        output.push(words.join(" "));
+        return;
+    }
+
+    let mut line = String::new();
+    for word in words {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            output.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        output.push(line);
+    }
+}
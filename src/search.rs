@@ -0,0 +1,168 @@
+// src/search.rs
+use crate::{note::Note, todo::Todo};
+use std::ops::Range;
+
+/// Characters of context kept on each side of a match in a snippet.
+const SNIPPET_CONTEXT: usize = 30;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ItemRef {
+    Note(usize),
+    Todo(usize),
+}
+
+pub struct SearchResult {
+    pub item: ItemRef,
+    pub title: String,
+    /// Text around the first match, from the title or a content line.
+    pub snippet: String,
+    /// Byte range of the match within `snippet`.
+    pub highlight: Range<usize>,
+}
+
+/// Byte range of the first case-insensitive occurrence of `needle`.
+pub fn find_ignore_case(haystack: &str, needle: &str) -> Option<Range<usize>> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() {
+        return None;
+    }
+
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut remaining = needle.as_slice();
+        for (offset, c) in haystack[start..].char_indices() {
+            for lower in c.to_lowercase() {
+                match remaining.split_first() {
+                    Some((&expected, rest)) if expected == lower => remaining = rest,
+                    _ => return None,
+                }
+            }
+            if remaining.is_empty() {
+                return Some(start..start + offset + c.len_utf8());
+            }
+        }
+        None
+    })
+}
+
+/// Searches titles, then content, of every note and todo that isn't
+/// archived.
+pub fn search(query: &str, notes: &[Note], todos: &[Todo]) -> Vec<SearchResult> {
+    searchable(notes, todos)
+        .filter_map(|(item, title, content)| {
+            let (snippet, highlight) = match find_ignore_case(title, query) {
+                Some(range) => (title.clone(), range),
+                None => {
+                    let range = find_ignore_case(content, query)?;
+                    snippet_around(content, range)
+                }
+            };
+            Some(SearchResult {
+                item,
+                title: title.clone(),
+                snippet,
+                highlight,
+            })
+        })
+        .collect()
+}
+
+/// How many items `search` would return, without building snippets; cheap
+/// enough to run on every keystroke.
+pub fn count_matches(query: &str, notes: &[Note], todos: &[Todo]) -> usize {
+    searchable(notes, todos)
+        .filter(|(_, title, content)| {
+            find_ignore_case(title, query).is_some() || find_ignore_case(content, query).is_some()
+        })
+        .count()
+}
+
+/// Every live (unarchived) note and todo with its title and content.
+fn searchable<'a>(
+    notes: &'a [Note],
+    todos: &'a [Todo],
+) -> impl Iterator<Item = (ItemRef, &'a String, &'a String)> {
+    let notes = notes
+        .iter()
+        .enumerate()
+        .filter(|(_, n)| !n.archived)
+        .map(|(i, n)| (ItemRef::Note(i), &n.title, &n.content));
+    let todos = todos
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| !t.archived)
+        .map(|(i, t)| (ItemRef::Todo(i), &t.title, &t.content));
+    notes.chain(todos)
+}
+
+/// Cuts the line containing `range` down to some context on either side,
+/// returning the snippet and the match range within it.
+fn snippet_around(content: &str, range: Range<usize>) -> (String, Range<usize>) {
+    let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[range.end..]
+        .find('\n')
+        .map_or(content.len(), |i| range.end + i);
+
+    let start = content[line_start..range.start]
+        .char_indices()
+        .rev()
+        .nth(SNIPPET_CONTEXT - 1)
+        .map_or(line_start, |(i, _)| line_start + i);
+    let end = content[range.end..line_end]
+        .char_indices()
+        .nth(SNIPPET_CONTEXT)
+        .map_or(line_end, |(i, _)| range.end + i);
+
+    let snippet = content[start..end].to_string();
+    (snippet, range.start - start..range.end - start)
+}
+
+/// Scores `query` as a case-insensitive subsequence of `title`, or `None`
+/// when its characters don't all appear in order. Consecutive matches and
+/// matches at the start of a word score higher; skipped characters cost a
+/// point each, so tighter matches rank first.
+pub fn fuzzy_score(query: &str, title: &str) -> Option<i64> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    let mut started = false;
+
+    for c in title.chars() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        let word_start = previous.is_none_or(|p| !p.is_alphanumeric());
+        if c.to_lowercase().eq(std::iter::once(wanted)) {
+            query.next();
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if word_start {
+                score += 3;
+            }
+            previous_matched = true;
+            started = true;
+        } else {
+            if started {
+                score -= 1;
+            }
+            previous_matched = false;
+        }
+        previous = Some(c);
+    }
+
+    query.peek().is_none().then_some(score)
+}
+
+/// Indices of `titles` that fuzzily match `query`, best match first; equal
+/// scores keep their original order. An empty query matches everything.
+pub fn fuzzy_rank(query: &str, titles: &[&str]) -> Vec<usize> {
+    let mut ranked: Vec<(usize, i64)> = titles
+        .iter()
+        .enumerate()
+        .filter_map(|(i, title)| Some((i, fuzzy_score(query, title)?)))
+        .collect();
+    ranked.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    ranked.into_iter().map(|(i, _)| i).collect()
+}
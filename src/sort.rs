@@ -0,0 +1,150 @@
+// src/sort.rs
+use crate::{note::Note, todo::Todo};
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Leave items in the order they were created or last arranged.
+    #[default]
+    None,
+    Title,
+    Created,
+    /// Most recently edited or renamed last (first when descending).
+    Modified,
+    /// Todos only: soonest due date first, undated todos last.
+    Due,
+    /// Todos only: overdue first, then by severity and due date.
+    Severity,
+}
+
+impl SortKey {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(SortKey::None),
+            "title" => Some(SortKey::Title),
+            "created" => Some(SortKey::Created),
+            "modified" => Some(SortKey::Modified),
+            "due" => Some(SortKey::Due),
+            "severity" => Some(SortKey::Severity),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::None => write!(f, "none"),
+            SortKey::Title => write!(f, "title"),
+            SortKey::Created => write!(f, "created"),
+            SortKey::Modified => write!(f, "modified"),
+            SortKey::Due => write!(f, "due"),
+            SortKey::Severity => write!(f, "severity"),
+        }
+    }
+}
+
+/// Fields shared by notes and todos that sorting can key on.
+pub trait Sortable {
+    fn title(&self) -> &str;
+    fn created_at(&self) -> &str;
+    fn updated_at(&self) -> &str;
+
+    fn due_date(&self) -> Option<&str> {
+        None
+    }
+
+    fn cmp_urgency(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl Sortable for Note {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn created_at(&self) -> &str {
+        &self.created_at
+    }
+
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+}
+
+impl Sortable for Todo {
+    fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn created_at(&self) -> &str {
+        &self.created_at
+    }
+
+    fn updated_at(&self) -> &str {
+        &self.updated_at
+    }
+
+    fn due_date(&self) -> Option<&str> {
+        self.due_date.as_deref()
+    }
+
+    fn cmp_urgency(&self, other: &Self) -> Ordering {
+        Todo::cmp_urgency(self, other)
+    }
+}
+
+fn compare<T: Sortable>(key: SortKey, a: &T, b: &T) -> Ordering {
+    match key {
+        SortKey::None => Ordering::Equal,
+        SortKey::Title => a.title().to_lowercase().cmp(&b.title().to_lowercase()),
+        SortKey::Created => compare_timestamps(a.created_at(), b.created_at()),
+        SortKey::Modified => compare_timestamps(a.updated_at(), b.updated_at()),
+        SortKey::Due => {
+            let parse = |date: Option<&str>| {
+                date.and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            };
+            parse(a.due_date()).cmp(&parse(b.due_date()))
+        }
+        SortKey::Severity => a.cmp_urgency(b),
+    }
+}
+
+fn compare_timestamps(a: &str, b: &str) -> Ordering {
+    let parse = |ts: &str| DateTime::parse_from_rfc3339(ts).ok();
+    parse(a).cmp(&parse(b)).then_with(|| a.cmp(b))
+}
+
+/// Indices of `items` in sorted order. The sort is stable, so `None` keeps
+/// the current arrangement. Items without a due date stay last under
+/// `Due` in either direction.
+pub fn sorted_order<T: Sortable>(items: &[T], key: SortKey, descending: bool) -> Vec<usize> {
+    let undated = |i: usize| key == SortKey::Due && items[i].due_date().is_none();
+    let mut order: Vec<usize> = (0..items.len()).collect();
+    order.sort_by(|&a, &b| {
+        undated(a).cmp(&undated(b)).then_with(|| {
+            let ordering = compare(key, &items[a], &items[b]);
+            if descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        })
+    });
+    order
+}
+
+/// Rearranges `items` so that position `n` holds the old `items[order[n]]`.
+pub fn reorder<T>(items: &mut Vec<T>, order: &[usize]) {
+    let mut slots: Vec<Option<T>> = items.drain(..).map(Some).collect();
+    items.extend(order.iter().filter_map(|&i| slots[i].take()));
+}
+
+/// Where an index from before `reorder` ends up afterwards.
+pub fn new_position(order: &[usize], old: Option<usize>) -> Option<usize> {
+    old.and_then(|i| order.iter().position(|&idx| idx == i))
+}
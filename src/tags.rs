@@ -0,0 +1,103 @@
+// src/tags.rs
+
+/// Characters that would make a tag ambiguous in the comma-joined list
+/// display or in CSV exports.
+const SEPARATORS: &[char] = &[',', ';', '|'];
+
+/// Trims a tag and rejects empty tags or ones containing separators or
+/// control characters, returning a message suitable for the status bar.
+pub fn normalize_tag(tag: &str) -> Result<String, String> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err("Tag cannot be empty".to_string());
+    }
+    if let Some(c) = tag
+        .chars()
+        .find(|c| SEPARATORS.contains(c) || c.is_control())
+    {
+        return Err(format!("Tag '{}' cannot contain {:?}", tag, c));
+    }
+    Ok(tag.to_string())
+}
+
+/// Tags an item must carry to be listed: all of them (`:tagged a,b`) or
+/// any of them (`:tagged-any a,b`). Tags compare case-insensitively.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TagFilter {
+    pub tags: Vec<String>,
+    pub any: bool,
+}
+
+impl TagFilter {
+    /// Builds a filter from a comma-separated list; `None` if it names no
+    /// tags.
+    pub fn parse(list: &str, any: bool) -> Option<Self> {
+        let tags: Vec<String> = list
+            .split(',')
+            .map(|tag| tag.trim().to_lowercase())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        (!tags.is_empty()).then_some(TagFilter { tags, any })
+    }
+
+    pub fn matches(&self, tags: &[String]) -> bool {
+        let has = |wanted: &String| tags.iter().any(|t| t.to_lowercase() == *wanted);
+        if self.any {
+            self.tags.iter().any(has)
+        } else {
+            self.tags.iter().all(has)
+        }
+    }
+}
+
+impl std::fmt::Display for TagFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let joiner = if self.any { " | " } else { " & " };
+        write!(f, "{}", self.tags.join(joiner))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+
+    #[test]
+    fn normalize_trims_valid_tags() {
+        assert_eq!(normalize_tag("  work ").unwrap(), "work");
+        assert_eq!(normalize_tag("to-do/2024").unwrap(), "to-do/2024");
+    }
+
+    #[test]
+    fn normalize_rejects_separators() {
+        for tag in ["a,b", "a;b", "a|b", ","] {
+            assert!(normalize_tag(tag).is_err(), "{:?} was accepted", tag);
+        }
+    }
+
+    #[test]
+    fn normalize_rejects_control_characters() {
+        for tag in ["a\tb", "a\nb", "a\u{7}b", "a\u{0}"] {
+            assert!(normalize_tag(tag).is_err(), "{:?} was accepted", tag);
+        }
+    }
+
+    #[test]
+    fn normalize_rejects_empty_tags() {
+        assert!(normalize_tag("").is_err());
+        assert!(normalize_tag("   ").is_err());
+    }
+
+    #[test]
+    fn add_tag_rejects_invalid_tags() {
+        let mut note = Note::new("Note".to_string(), "2024-01-01T00:00:00+00:00".to_string());
+        for tag in ["a,b", "a;b", "a|b", "a\nb", ""] {
+            assert!(note.add_tag(tag).is_err(), "{:?} was accepted", tag);
+        }
+        assert!(note.tags.is_empty());
+
+        note.add_tag(" work ").unwrap();
+        note.add_tag("work").unwrap();
+        assert_eq!(note.tags, ["work"]);
+    }
+}
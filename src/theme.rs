@@ -0,0 +1,139 @@
+// src/theme.rs
+use crate::config::Config;
+use crate::todo::Severity;
+use ratatui::style::{Color, Modifier, Style};
+use std::{env, str::FromStr};
+
+/// Names accepted by `:theme` and the `theme` config key.
+pub const THEME_NAMES: [&str; 3] = ["dark", "light", "solarized"];
+
+pub struct Theme {
+    pub critical: Color,
+    pub high: Color,
+    pub medium: Color,
+    pub low: Color,
+    pub info: Color,
+    pub highlight: Style,
+    /// Mode label, command line, search matches and pinned notes.
+    pub emphasis: Color,
+    /// Active filter indicators and Markdown headings.
+    pub accent: Color,
+    /// Secondary text such as the word count.
+    pub muted: Color,
+    pub completed: Color,
+    pub overdue: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            critical: Color::Red,
+            high: Color::Rgb(255, 135, 0),
+            medium: Color::Yellow,
+            low: Color::Cyan,
+            info: Color::Gray,
+            highlight: Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            emphasis: Color::Yellow,
+            accent: Color::Cyan,
+            muted: Color::DarkGray,
+            completed: Color::Green,
+            overdue: Color::Red,
+        }
+    }
+}
+
+impl Theme {
+    /// One of the built-in color schemes in `THEME_NAMES`.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Theme::default()),
+            // Yellow and cyan wash out on white, so use darker shades.
+            "light" => Some(Theme {
+                high: Color::Rgb(215, 95, 0),
+                medium: Color::Rgb(175, 135, 0),
+                low: Color::Blue,
+                info: Color::DarkGray,
+                emphasis: Color::Blue,
+                accent: Color::Magenta,
+                completed: Color::Rgb(0, 135, 0),
+                ..Theme::default()
+            }),
+            "solarized" => Some(Theme {
+                critical: Color::Rgb(220, 50, 47),
+                high: Color::Rgb(203, 75, 22),
+                medium: Color::Rgb(181, 137, 0),
+                low: Color::Rgb(38, 139, 210),
+                info: Color::Rgb(88, 110, 117),
+                emphasis: Color::Rgb(181, 137, 0),
+                accent: Color::Rgb(42, 161, 152),
+                muted: Color::Rgb(88, 110, 117),
+                completed: Color::Rgb(133, 153, 0),
+                overdue: Color::Rgb(220, 50, 47),
+                ..Theme::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Builds the theme from the configured preset (dark if unknown),
+    /// overriding any severity whose entry in `severity_colors` parses as a
+    /// color name or `#rrggbb` value.
+    pub fn from_config(config: &Config) -> Self {
+        let mut theme = Theme {
+            highlight: highlight_style(&config.highlight),
+            ..Theme::preset(&config.theme).unwrap_or_default()
+        };
+        for (name, value) in &config.severity_colors {
+            let Ok(color) = Color::from_str(value) else {
+                continue;
+            };
+            match name.to_lowercase().as_str() {
+                "critical" => theme.critical = color,
+                "high" => theme.high = color,
+                "medium" => theme.medium = color,
+                "low" => theme.low = color,
+                "info" => theme.info = color,
+                _ => {}
+            }
+        }
+        theme
+    }
+
+    pub fn severity_color(&self, severity: &Severity) -> Color {
+        match severity {
+            Severity::Critical => self.critical,
+            Severity::High => self.high,
+            Severity::Medium => self.medium,
+            Severity::Low => self.low,
+            Severity::Info => self.info,
+        }
+    }
+}
+
+/// Resolves the `highlight` setting. "auto" keeps the blue selection bar
+/// only when `COLORFGBG` reports a dark background; light, transparent, or
+/// unknown backgrounds get reversed video, which works on any palette.
+fn highlight_style(setting: &str) -> Style {
+    let reversed = Style::default().add_modifier(Modifier::REVERSED | Modifier::BOLD);
+    let on = |color: Color| Style::default().bg(color).add_modifier(Modifier::BOLD);
+    match setting {
+        "reversed" => reversed,
+        "auto" => {
+            if terminal_background_is_dark() {
+                on(Color::Blue)
+            } else {
+                reversed
+            }
+        }
+        other => Color::from_str(other).map_or(reversed, on),
+    }
+}
+
+/// `COLORFGBG` looks like "15;0": the last field is the background's ANSI
+/// index, where 0-6 and 8 are dark.
+fn terminal_background_is_dark() -> bool {
+    env::var("COLORFGBG")
+        .ok()
+        .and_then(|value| value.rsplit(';').next()?.parse::<u8>().ok())
+        .is_some_and(|bg| bg <= 6 || bg == 8)
+}
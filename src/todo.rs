@@ -1,16 +1,35 @@
 // src/todo.rs
-use chrono::{Local, NaiveDate};
+use crate::tags::normalize_tag;
+use chrono::{DateTime, Days, Local, Months, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct Todo {
     pub title: String,
     pub content: String,
     pub created_at: String,
+    /// RFC3339 time of the last edit or rename; see `Note::updated_at`.
+    #[serde(default)]
+    pub updated_at: String,
     pub completed: bool,
     pub tags: Vec<String>,
     pub due_date: Option<String>,
     pub severity: Severity,
+    #[serde(default)]
+    pub completed_at: Option<String>,
+    #[serde(default)]
+    pub archived: bool,
+    /// `id` of the note this todo was spawned from.
+    #[serde(default)]
+    pub source_note_id: Option<u64>,
+    /// `created_at` link written by older versions; resolved to
+    /// `source_note_id` on load and never written back.
+    #[serde(default, skip_serializing)]
+    pub source_note: Option<String>,
+    /// Completing a recurring todo queues a fresh copy due one period later.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
 }
 
 #[derive(Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -22,23 +41,136 @@ pub enum Severity {
     Info,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl Recurrence {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(Recurrence::Daily),
+            "weekly" => Some(Recurrence::Weekly),
+            "monthly" => Some(Recurrence::Monthly),
+            _ => None,
+        }
+    }
+
+    /// The date one period after `date`; month ends clamp, so Jan 31
+    /// becomes Feb 28 (or 29).
+    pub fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily => date.checked_add_days(Days::new(1)),
+            Recurrence::Weekly => date.checked_add_days(Days::new(7)),
+            Recurrence::Monthly => date.checked_add_months(Months::new(1)),
+        }
+    }
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily => write!(f, "daily"),
+            Recurrence::Weekly => write!(f, "weekly"),
+            Recurrence::Monthly => write!(f, "monthly"),
+        }
+    }
+}
+
+impl Severity {
+    /// All levels from most to least urgent.
+    pub const ALL: [Severity; 5] = [
+        Severity::Critical,
+        Severity::High,
+        Severity::Medium,
+        Severity::Low,
+        Severity::Info,
+    ];
+
+    /// Urgency used for ordering, higher is more urgent. Spelled out so
+    /// reordering the variants doesn't change how todos sort.
+    pub fn rank(&self) -> u8 {
+        match self {
+            Severity::Critical => 4,
+            Severity::High => 3,
+            Severity::Medium => 2,
+            Severity::Low => 1,
+            Severity::Info => 0,
+        }
+    }
+
+    /// One level more urgent; Critical stays Critical.
+    pub fn raised(&self) -> Severity {
+        Severity::ALL.iter().filter(|s| *s > self).min().unwrap_or(self).clone()
+    }
+
+    /// One level less urgent; Info stays Info.
+    pub fn lowered(&self) -> Severity {
+        Severity::ALL.iter().filter(|s| *s < self).max().unwrap_or(self).clone()
+    }
+}
+
+impl PartialOrd for Severity {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Ordered by urgency, so `Critical` is the greatest.
+impl Ord for Severity {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
 impl Todo {
     pub fn new(title: String, created_at: String) -> Self {
         Todo {
             title,
             content: String::new(),
+            updated_at: created_at.clone(),
             created_at,
             completed: false,
             tags: Vec::new(),
             due_date: None,
             severity: Severity::Medium,
+            completed_at: None,
+            archived: false,
+            source_note_id: None,
+            source_note: None,
+            recurrence: None,
         }
     }
 
-    pub fn add_tag(&mut self, tag: String) {
+    /// The next instance of a recurring todo: incomplete, created now and
+    /// due one period after this one's due date (or today, if it had none).
+    pub fn next_occurrence(&self) -> Option<Todo> {
+        let recurrence = self.recurrence?;
+        let base = self
+            .due_date
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .unwrap_or_else(|| Local::now().date_naive());
+        let due = recurrence.next_after(base)?;
+        let mut next = self.clone();
+        next.created_at = Local::now().to_rfc3339();
+        next.updated_at = next.created_at.clone();
+        next.completed = false;
+        next.completed_at = None;
+        next.archived = false;
+        next.due_date = Some(due.format("%Y-%m-%d").to_string());
+        Some(next)
+    }
+
+    pub fn add_tag(&mut self, tag: &str) -> Result<(), String> {
+        let tag = normalize_tag(tag)?;
         if !self.tags.contains(&tag) {
             self.tags.push(tag);
         }
+        Ok(())
     }
 
     pub fn remove_tag(&mut self, tag: &str) {
@@ -55,6 +187,36 @@ impl Todo {
         self.severity = severity;
     }
 
+    /// Whether this completed todo has been done for at least `delay`.
+    pub fn completed_for_at_least(&self, delay: chrono::Duration) -> bool {
+        if !self.completed {
+            return false;
+        }
+        self.completed_at
+            .as_deref()
+            .and_then(|ts| DateTime::parse_from_rfc3339(ts).ok())
+            .is_some_and(|done| Local::now().signed_duration_since(done) >= delay)
+    }
+
+    /// Days from today until the due date, negative once it has passed.
+    pub fn due_in_days(&self) -> Option<i64> {
+        let due = NaiveDate::parse_from_str(self.due_date.as_deref()?, "%Y-%m-%d").ok()?;
+        Some((due - Local::now().date_naive()).num_days())
+    }
+
+    /// Orders the more urgent todo first: overdue ones, then by severity,
+    /// then by the soonest due date with undated todos last.
+    pub fn cmp_urgency(&self, other: &Todo) -> Ordering {
+        let key = |todo: &Todo| {
+            let due = todo
+                .due_date
+                .as_deref()
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
+            (!todo.is_overdue(), Reverse(todo.severity.rank()), due.is_none(), due)
+        };
+        key(self).cmp(&key(other))
+    }
+
     pub fn is_overdue(&self) -> bool {
         if let Some(due_date_str) = &self.due_date {
             if let Ok(date) = NaiveDate::parse_from_str(due_date_str, "%Y-%m-%d") {
@@ -75,4 +237,46 @@ impl std::fmt::Display for Severity {
             Severity::Info => write!(f, "Info"),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Which todos `:filter` keeps in the list.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TodoFilter {
+    #[default]
+    All,
+    Done,
+    Pending,
+    Overdue,
+}
+
+impl TodoFilter {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "all" => Some(TodoFilter::All),
+            "done" => Some(TodoFilter::Done),
+            "pending" => Some(TodoFilter::Pending),
+            "overdue" => Some(TodoFilter::Overdue),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            TodoFilter::All => true,
+            TodoFilter::Done => todo.completed,
+            TodoFilter::Pending => !todo.completed,
+            TodoFilter::Overdue => todo.is_overdue(),
+        }
+    }
+}
+
+impl std::fmt::Display for TodoFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TodoFilter::All => write!(f, "all"),
+            TodoFilter::Done => write!(f, "done"),
+            TodoFilter::Pending => write!(f, "pending"),
+            TodoFilter::Overdue => write!(f, "overdue"),
+        }
+    }
+}
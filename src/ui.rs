@@ -1,14 +1,40 @@
 // src/ui.rs
 use crate::app::{App, AppMode, AppSection};
+use crate::commands;
+use crate::dates;
+use crate::editor;
+use crate::markdown;
+use crate::search::ItemRef;
+use crate::todo::{Severity, TodoFilter};
 use ratatui::{
     backend::Backend,
-    layout::{Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
+use chrono::{Datelike, Local, Months, Weekday};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Smallest terminal the normal layout is drawn in; below this only a
+/// notice is shown until the window grows again.
+const MIN_WIDTH: u16 = 20;
+const MIN_HEIGHT: u16 = 8;
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    if size.width < MIN_WIDTH || size.height < MIN_HEIGHT {
+        let notice = Paragraph::new("Terminal too small").wrap(Wrap { trim: true });
+        f.render_widget(notice, size);
+        return;
+    }
+
+    if app.zen && matches!(app.mode, AppMode::Editing) {
+        draw_zen(f, app);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
@@ -25,6 +51,187 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &App) {
     draw_status_bar(f, app, chunks[0]);
     draw_main_content(f, app, chunks[1]);
     draw_command_line(f, app, chunks[2]);
+
+    match app.mode {
+        AppMode::SeverityPicker => draw_severity_picker(f, app),
+        AppMode::Inspect => draw_inspect_popup(f, app),
+        AppMode::Goto => draw_goto_popup(f, app),
+        AppMode::QuitPrompt => draw_quit_prompt(f, app),
+        AppMode::DatePicker => draw_date_picker(f, app),
+        _ => {}
+    }
+}
+
+/// A `width` x `height` rectangle centered in `area`, shrunk to fit.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+fn draw_inspect_popup<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    let area = centered_rect(size.width * 4 / 5, size.height * 4 / 5, size);
+    let popup = Paragraph::new(app.inspect_text.as_str())
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Inspect (j/k scroll, Esc close)"),
+        )
+        .scroll((app.inspect_scroll, 0));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn draw_goto_popup<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let size = f.size();
+    let area = centered_rect(size.width * 3 / 5, size.height * 3 / 5, size);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Go to (Up/Down select, Enter open, Esc close)");
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)].as_ref())
+        .split(inner);
+    let prompt = Paragraph::new(format!("> {}", app.goto_query))
+        .style(Style::default().fg(app.theme.emphasis));
+    f.render_widget(prompt, chunks[0]);
+
+    let items: Vec<ListItem> = app
+        .goto_matches
+        .iter()
+        .filter_map(|item| {
+            let (kind, title) = match *item {
+                ItemRef::Note(i) => ("[Note]", &app.notes.get(i)?.title),
+                ItemRef::Todo(i) => ("[Todo]", &app.todos.get(i)?.title),
+            };
+            let text = format!("{} {}", kind, title);
+            Some(ListItem::new(truncate_to_width(&text, chunks[1].width as usize)))
+        })
+        .collect();
+    let list = List::new(items).highlight_style(app.theme.highlight);
+    let mut list_state = ListState::default();
+    if !app.goto_matches.is_empty() {
+        list_state.select(Some(app.selected_goto));
+    }
+    f.render_stateful_widget(list, chunks[1], &mut list_state);
+}
+
+fn draw_quit_prompt<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let key = |k: &'static str| {
+        Span::styled(k, Style::default().fg(app.theme.emphasis).add_modifier(Modifier::BOLD))
+    };
+    let lines = vec![
+        Line::from("There are unsaved changes."),
+        Line::from(""),
+        Line::from(vec![key("s"), Span::raw("  Save and quit")]),
+        Line::from(vec![key("d"), Span::raw("  Quit without saving")]),
+        Line::from(vec![key("c"), Span::raw("  Cancel (Esc)")]),
+    ];
+    let area = centered_rect(34, lines.len() as u16 + 2, f.size());
+    let popup = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title("Quit"));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+/// Month calendar for `:cal`, weeks starting on Monday, with the chosen
+/// day highlighted and today in bold.
+fn draw_date_picker<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let chosen = app.date_choice;
+    let today = Local::now().date_naive();
+    let first = chosen.with_day(1).unwrap_or(chosen);
+    let days_in_month = first
+        .checked_add_months(Months::new(1))
+        .map_or(31, |next| (next - first).num_days() as u32);
+
+    let mut lines = vec![
+        Line::styled(
+            format!("{:^20}", first.format("%B %Y").to_string()),
+            Style::default().fg(app.theme.emphasis).add_modifier(Modifier::BOLD),
+        ),
+        Line::styled("Mo Tu We Th Fr Sa Su", Style::default().fg(app.theme.muted)),
+    ];
+    let mut week = vec![Span::raw("   ".repeat(first.weekday().num_days_from_monday() as usize))];
+    for day in 1..=days_in_month {
+        let date = first.with_day(day).unwrap_or(first);
+        let mut style = Style::default();
+        if date == today {
+            style = style.add_modifier(Modifier::BOLD);
+        }
+        if date == chosen {
+            style = style.patch(app.theme.highlight);
+        }
+        week.push(Span::styled(format!("{:>2}", day), style));
+        if date.weekday() == Weekday::Sun {
+            lines.push(Line::from(std::mem::take(&mut week)));
+        } else {
+            week.push(Span::raw(" "));
+        }
+    }
+    if !week.is_empty() {
+        lines.push(Line::from(week));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::styled("PgUp/PgDn month", Style::default().fg(app.theme.muted)));
+
+    let area = centered_rect(24, lines.len() as u16 + 2, f.size());
+    let popup = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title("Due date (Enter set)"));
+    f.render_widget(Clear, area);
+    f.render_widget(popup, area);
+}
+
+fn draw_severity_picker<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let items: Vec<ListItem> = Severity::ALL
+        .iter()
+        .map(|severity| {
+            ListItem::new(severity.to_string())
+                .style(Style::default().fg(app.theme.severity_color(severity)))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title("Severity"))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+
+    let area = centered_rect(24, Severity::ALL.len() as u16 + 2, f.size());
+    let mut list_state = ListState::default();
+    list_state.select(Some(app.severity_choice));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+/// Full-screen writing view: only the open note, centered with wide margins.
+fn draw_zen<B: Backend>(f: &mut Frame<B>, app: &App) {
+    let Some(note) = app.current_note.and_then(|i| app.notes.get(i)) else {
+        return;
+    };
+
+    let size = f.size();
+    let width = size.width.saturating_sub(4).min(80);
+    let height = size.height.saturating_sub(4);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + (size.height - height) / 2,
+        width,
+        height,
+    );
+
+    let header = vec![
+        Line::styled(note.title.clone(), Style::default().add_modifier(Modifier::BOLD)),
+        Line::from(""),
+    ];
+    draw_editor_body(f, app, area, header, &note.content, note.wrap, true);
 }
 
 fn draw_status_bar<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
@@ -35,70 +242,227 @@ fn draw_status_bar<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         AppMode::MainMenu => "MENU",
         AppMode::Help => "HELP",
         AppMode::Renaming => "RENAME",
+        AppMode::Confirm => "CONFIRM",
+        AppMode::SeverityPicker => "SEVERITY",
+        AppMode::Inspect => "INSPECT",
+        AppMode::Search => "SEARCH",
+        AppMode::Restore => "RESTORE",
+        AppMode::Goto => "GOTO",
+        AppMode::Password => "PASSPHRASE",
+        AppMode::QuitPrompt => "QUIT",
+        AppMode::DatePicker => "DATE",
     };
-    let mode_widget = Paragraph::new(mode_text).style(Style::default().fg(Color::Yellow));
-    let mode_area = Rect::new(
-        area.width.saturating_sub(mode_text.len() as u16 + 4),
-        area.y + 1,
-        mode_text.len() as u16 + 2,
-        1,
-    );
-    f.render_widget(mode_widget, mode_area);
+
+    let mut indicators = Vec::new();
+    if matches!(app.section, AppSection::Todos) && app.todo_filter != TodoFilter::All {
+        let filter = format!("filter: {}", app.todo_filter);
+        indicators.push(Span::styled(filter, Style::default().fg(app.theme.accent)));
+        indicators.push(Span::raw("   "));
+    }
+    if app.show_archived {
+        indicators.push(Span::styled("archived", Style::default().fg(app.theme.accent)));
+        indicators.push(Span::raw("   "));
+    }
+    if let Some(tags) = &app.tag_filter {
+        let filter = format!("tag: {}", tags);
+        indicators.push(Span::styled(filter, Style::default().fg(app.theme.accent)));
+        indicators.push(Span::raw("   "));
+    }
+    if let Some(stats) = item_stats(app) {
+        indicators.push(Span::styled(stats, Style::default().fg(app.theme.muted)));
+        indicators.push(Span::raw("   "));
+    }
+    indicators.push(Span::styled(mode_text, Style::default().fg(app.theme.emphasis)));
+    indicators.push(Span::raw("   "));
+    indicators.push(Span::styled(app.clock.as_str(), Style::default().fg(app.theme.muted)));
+    indicators.push(Span::raw(" "));
+    let indicators = Line::from(indicators);
+
+    // Always reserve the corner so the message doesn't shift as the
+    // modified marker comes and goes.
+    let modified = if app.unsaved_changes {
+        Span::styled("[+]", Style::default().fg(app.theme.overdue).add_modifier(Modifier::BOLD))
+    } else {
+        Span::raw("")
+    };
+
+    let block = Block::default().borders(Borders::ALL).title(app.breadcrumb());
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(0),
+            Constraint::Length(indicators.width() as u16),
+        ])
+        .split(inner);
 
     let status_message = app
         .status_message
         .as_deref()
         .unwrap_or("Terminal Notes - Press : for commands, Ctrl+Q to quit");
-    let status_widget =
-        Paragraph::new(status_message).block(Block::default().borders(Borders::ALL).title("Status"));
-    f.render_widget(status_widget, area);
+    f.render_widget(Paragraph::new(Line::from(modified)), chunks[0]);
+    f.render_widget(Paragraph::new(status_message), chunks[1]);
+    f.render_widget(Paragraph::new(indicators), chunks[2]);
+}
+
+/// Word and character counts for the active note, or the line count for
+/// the active todo; `None` outside the lists or with nothing selected.
+fn item_stats(app: &App) -> Option<String> {
+    if !matches!(app.mode, AppMode::Normal | AppMode::Editing | AppMode::Command) {
+        return None;
+    }
+    match app.section {
+        AppSection::Notes => {
+            let note = app.current_note.or(app.selected_note).and_then(|i| app.notes.get(i))?;
+            Some(format!(
+                "{} words / {} chars",
+                note.word_count(),
+                note.content.chars().count()
+            ))
+        }
+        AppSection::Todos => {
+            let todo = app.current_todo.or(app.selected_todo).and_then(|i| app.todos.get(i))?;
+            Some(format!("{} lines", todo.content.lines().count()))
+        }
+        AppSection::Catalog | AppSection::Help => None,
+    }
 }
 
 fn draw_main_content<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     match app.mode {
         AppMode::MainMenu => draw_main_menu(f, app, area),
         AppMode::Help => draw_help(f, area),
+        AppMode::Search => draw_search_results(f, app, area),
+        AppMode::Restore => draw_backup_list(f, app, area),
         _ => match app.section {
             AppSection::Notes => draw_notes_section(f, app, area),
             AppSection::Todos => draw_todos_section(f, app, area),
+            AppSection::Catalog => draw_catalog_section(f, app, area),
             _ => {}
         },
     }
 }
 
+fn draw_search_results<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let match_style = Style::default()
+        .fg(app.theme.emphasis)
+        .add_modifier(Modifier::BOLD);
+    let items: Vec<ListItem> = app
+        .search_results
+        .iter()
+        .map(|result| {
+            let kind = match result.item {
+                ItemRef::Note(_) => "[Note]",
+                ItemRef::Todo(_) => "[Todo]",
+            };
+            let snippet = &result.snippet;
+            let highlight = result.highlight.clone();
+            ListItem::new(Line::from(vec![
+                Span::raw(format!("{} {} — ", kind, result.title)),
+                Span::raw(snippet[..highlight.start].replace('\n', " ")),
+                Span::styled(snippet[highlight.clone()].to_string(), match_style),
+                Span::raw(snippet[highlight.end..].replace('\n', " ")),
+            ]))
+        })
+        .collect();
+
+    let title = format!("Results for '{}' (Enter open, Esc close)", app.search_query);
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(app.theme.highlight);
+
+    let mut list_state = ListState::default();
+    if !app.search_results.is_empty() {
+        list_state.select(Some(app.selected_result));
+    }
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
+fn draw_backup_list<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let items: Vec<ListItem> = app
+        .backups
+        .iter()
+        .map(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            ListItem::new(name.into_owned())
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Restore backup (Enter restore, Esc cancel)"),
+        )
+        .highlight_style(app.theme.highlight);
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(app.selected_backup));
+    f.render_stateful_widget(list, area, &mut list_state);
+}
+
 fn draw_command_line<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    if let AppMode::Password = app.mode {
+        let masked = "*".repeat(app.password_buffer.chars().count());
+        let prompt = Paragraph::new(format!("Passphrase: {}", masked))
+            .style(Style::default().fg(app.theme.emphasis));
+        f.render_widget(prompt, area);
+    }
     if let AppMode::Command | AppMode::Renaming = app.mode {
         let command_text = format!(":{}", app.command_buffer);
         let command_widget =
-            Paragraph::new(command_text).style(Style::default().fg(Color::Yellow));
+            Paragraph::new(command_text).style(Style::default().fg(app.theme.emphasis));
         f.render_widget(command_widget, area);
     }
 }
 
 fn draw_main_menu<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let menu_items = &["Notes", "Todos", "Help"];
-    let items: Vec<ListItem> = menu_items
-        .iter()
-        .map(|&item| ListItem::new(item))
-        .collect();
+    // Counts leave out archived notes and todos, like the section lists do.
+    let notes = app.notes.iter().filter(|n| !n.archived).count();
+    let live_todos = || app.todos.iter().filter(|t| !t.archived);
+    let todos = live_todos().count();
+    let overdue = live_todos().filter(|t| t.is_overdue()).count();
+    let catalogs = app.catalogs.len();
+
+    let todo_line = if overdue > 0 {
+        Line::from(vec![
+            Span::raw(format!("Todos ({}, ", todos)),
+            Span::styled(
+                format!("{} overdue", overdue),
+                Style::default().fg(app.theme.overdue).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(")"),
+        ])
+    } else {
+        Line::from(format!("Todos ({})", todos))
+    };
+    let items = vec![
+        ListItem::new(format!("Notes ({})", notes)),
+        ListItem::new(todo_line),
+        ListItem::new(format!("Catalog ({})", catalogs)),
+        ListItem::new("Help"),
+    ];
 
     let menu = List::new(items)
         .block(Block::default().borders(Borders::ALL).title("Main Menu"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::Blue)
-                .add_modifier(Modifier::BOLD),
-        );
+        .highlight_style(app.theme.highlight);
 
     let mut list_state = ListState::default();
     list_state.select(Some(app.selected_menu_item));
     f.render_stateful_widget(menu, area, &mut list_state);
 }
 
+/// Columns for a section's list and editor panes, split at `list_width`.
+fn list_and_editor(app: &App) -> [Constraint; 2] {
+    [Constraint::Percentage(app.list_width), Constraint::Percentage(100 - app.list_width)]
+}
+
 fn draw_notes_section<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .constraints(list_and_editor(app).as_ref())
         .split(area);
 
     draw_notes_list(f, app, chunks[0]);
@@ -106,98 +470,411 @@ fn draw_notes_section<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
 }
 
 fn draw_notes_list<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .notes
+    let order = app.note_display_order();
+    let items: Vec<ListItem> = order
         .iter()
+        .map(|&i| &app.notes[i])
         .map(|note| {
-            let tag_info = if !note.tags.is_empty() {
-                format!(" [{}]", note.tags.join(", "))
+            let progress = note
+                .checklist_progress()
+                .map(|(checked, total)| format!(" {}/{}", checked, total))
+                .unwrap_or_default();
+            let pin = if note.pinned { "📌 " } else { "" };
+            let new = new_marker(&note.created_at);
+            let text = format!("{}{}{}{}", pin, new, note.title, progress);
+            let style = if note.pinned {
+                Style::default().fg(app.theme.emphasis).add_modifier(Modifier::BOLD)
             } else {
-                String::new()
+                Style::default()
             };
-            ListItem::new(format!("{}{}", note.title, tag_info))
+            ListItem::new(with_tag_chips(text, &note.tags, list_text_width(area))).style(style)
         })
         .collect();
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Notes"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::Blue)
-                .add_modifier(Modifier::BOLD),
-        );
+    let block = Block::default().borders(Borders::ALL).title("Notes");
+    if order.is_empty() {
+        let hint = if app.show_archived {
+            ["No archived notes", ""]
+        } else if app.notes.iter().any(|n| !n.archived) {
+            ["No notes match the tag filter", ":tagged with no tags clears it"]
+        } else {
+            ["No notes yet", "press : then nn (or Ctrl+N) to create one"]
+        };
+        draw_hint(f, app, block.inner(area), &hint);
+    }
+    let list = List::new(items).block(block).highlight_style(app.theme.highlight);
 
-    let mut list_state = ListState::default();
-    list_state.select(app.selected_note);
+    let mut list_state = ListState::default()
+        .with_offset(app.notes_list_offset.get())
+        .with_selected(display_position(&order, app.selected_note));
     f.render_stateful_widget(list, area, &mut list_state);
+    app.notes_list_offset.set(list_state.offset());
+}
+
+/// Leading dot marking list items created today.
+fn new_marker(created_at: &str) -> &'static str {
+    if dates::is_today(created_at) {
+        "• "
+    } else {
+        ""
+    }
+}
+
+/// Chip backgrounds; each tag keeps the same one wherever it is shown.
+const TAG_COLORS: [Color; 8] = [
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightRed,
+];
+
+/// Tags as chips: padded, black on a background picked by hashing the
+/// lowercased tag (FNV-1a), so the color is the same on every run.
+fn tag_chips(tags: &[String]) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    for tag in tags {
+        let hash = tag
+            .to_lowercase()
+            .bytes()
+            .fold(0x811c_9dc5_u32, |hash, b| (hash ^ b as u32).wrapping_mul(0x0100_0193));
+        let color = TAG_COLORS[hash as usize % TAG_COLORS.len()];
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            format!(" {} ", tag),
+            Style::default().fg(Color::Black).bg(color),
+        ));
+    }
+    spans
+}
+
+/// `text` followed by as many of its tag chips as fit in `width` cells,
+/// always leaving at least half the row (or all of `text`) for the text.
+fn with_tag_chips(text: String, tags: &[String], width: usize) -> Line<'static> {
+    let mut budget = width.saturating_sub(text.width().min(width / 2));
+    let mut chips = Vec::new();
+    for chip in tag_chips(tags).chunks(2) {
+        let chip_width: usize = chip.iter().map(Span::width).sum();
+        if chip_width > budget {
+            break;
+        }
+        budget -= chip_width;
+        chips.extend_from_slice(chip);
+    }
+    let chips_width: usize = chips.iter().map(Span::width).sum();
+    let mut spans = vec![Span::raw(truncate_to_width(&text, width.saturating_sub(chips_width)))];
+    spans.extend(chips);
+    Line::from(spans)
+}
+
+/// The `Tags:` header line of the editor panes.
+fn tags_line(tags: &[String]) -> Line<'static> {
+    if tags.is_empty() {
+        return Line::from("Tags: None");
+    }
+    let mut spans = vec![Span::raw("Tags:")];
+    spans.extend(tag_chips(tags));
+    Line::from(spans)
+}
+
+/// Columns available for item text inside a bordered list.
+fn list_text_width(area: Rect) -> usize {
+    area.width.saturating_sub(2) as usize
+}
+
+/// Shortens `text` to at most `max_width` terminal cells, measuring wide
+/// characters (emoji, CJK) as two cells and ending with an ellipsis.
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let width = c.width().unwrap_or(0);
+        if used + width + 1 > max_width {
+            break;
+        }
+        truncated.push(c);
+        used += width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Maps a selected vector index to its row in the rendered list.
+fn display_position(order: &[usize], selected: Option<usize>) -> Option<usize> {
+    selected.and_then(|i| order.iter().position(|&idx| idx == i))
 }
 
 fn draw_note_editor<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let editor_block = Block::default().borders(Borders::ALL).title("Editor");
+    let preview = app.preview && !matches!(app.mode, AppMode::Editing);
+    let title = if preview { "Preview" } else { "Editor" };
+    let editor_block = Block::default().borders(Borders::ALL).title(title);
     let inner_area = editor_block.inner(area);
     f.render_widget(editor_block, area);
 
     if let Some(note) = app.current_note.and_then(|i| app.notes.get(i)) {
-        let header = format!(
-            "Title: {}\nCreated: {}\nTags: {}\n\n",
-            note.title,
-            note.created_at,
-            if note.tags.is_empty() {
-                "None".to_string()
+        let mut header = vec![
+            Line::from(format!("Title: {}", note.title)),
+            Line::from(format!("Created: {}", dates::friendly_timestamp(&note.created_at))),
+            Line::from(format!("Modified: {}", dates::friendly_timestamp(&note.updated_at))),
+            tags_line(&note.tags),
+        ];
+        if let Some(goal) = note.word_goal {
+            header.push(goal_line(app, note.word_count(), goal));
+        }
+        header.push(Line::from(""));
+        if preview {
+            let mut lines = header;
+            lines.extend(markdown::render(&note.content, app.theme.accent));
+            let mut body = Paragraph::new(lines);
+            if note.wrap {
+                body = body.wrap(Wrap { trim: false });
             } else {
-                note.tags.join(", ")
+                body = body.scroll((0, app.editor_hscroll.get() as u16));
             }
-        );
-        let content = format!("{}{}", header, note.content);
-        let editor_text = Paragraph::new(content).wrap(ratatui::widgets::Wrap { trim: true });
-        f.render_widget(editor_text, inner_area);
+            f.render_widget(body, inner_area);
+        } else {
+            draw_editor_body(f, app, inner_area, header, &note.content, note.wrap, true);
+        }
+    } else if !app.note_display_order().is_empty() {
+        draw_hint(f, app, inner_area, &["Nothing open", "select a note and press Enter"]);
+    }
+}
+
+/// Muted lines centered in `area`, standing in for an empty list or
+/// editor so it says what to do next.
+fn draw_hint<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect, lines: &[&str]) {
+    let lines: Vec<Line> = lines.iter().filter(|l| !l.is_empty()).map(|&l| Line::from(l)).collect();
+    let top = area.height.saturating_sub(lines.len() as u16) / 2;
+    let area = Rect { y: area.y + top, height: area.height - top, ..area };
+    let hint = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .style(Style::default().fg(app.theme.muted));
+    f.render_widget(hint, area);
+}
+
+/// `Goal: 120/500 words (24%)`, green once the goal is met.
+fn goal_line(app: &App, words: usize, goal: usize) -> Line<'static> {
+    let percent = words * 100 / goal.max(1);
+    let progress = format!("{}/{} words ({}%)", words, goal, percent);
+    let style = if words >= goal {
+        Style::default().fg(app.theme.completed).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+    };
+    Line::from(vec![Span::raw("Goal: "), Span::styled(progress, style)])
+}
+
+/// Renders `header` followed by `content`, wrapped to the pane width when
+/// `wrap` is set and otherwise scrolled sideways by `app.editor_hscroll`.
+/// While editing, the view scrolls to keep the cursor visible and the
+/// terminal cursor is placed at `app.cursor_pos`. In Normal mode the line
+/// `dd` would delete is underlined instead, if `content` is the open item.
+fn draw_editor_body<B: Backend>(
+    f: &mut Frame<B>,
+    app: &App,
+    area: Rect,
+    header: Vec<Line>,
+    content: &str,
+    wrap: bool,
+    open: bool,
+) {
+    let editing = matches!(app.mode, AppMode::Editing);
+    let marked = open && matches!(app.mode, AppMode::Normal);
+    let width = area.width as usize;
+    let rows = if wrap {
+        editor::wrap_rows(content, width)
+    } else {
+        editor::line_rows(content)
+    };
+    let (row, column) = editor::cursor_row_col(content, &rows, app.cursor_pos);
+    let cursor_line = header.len() + row;
+    let scroll = if editing || marked {
+        cursor_line.saturating_sub((area.height as usize).saturating_sub(1))
+    } else {
+        0
+    };
+    let mut hscroll = if wrap { 0 } else { app.editor_hscroll.get() };
+    if editing && !wrap && width > 0 {
+        hscroll = hscroll.min(column).max((column + 1).saturating_sub(width));
+        app.editor_hscroll.set(hscroll);
+    }
+
+    let cursor = editor::clamp_to_boundary(content, app.cursor_pos);
+    let cursor_line_bytes =
+        editor::line_start(content, cursor)..=editor::line_end(content, cursor);
+    let mut lines = header;
+    lines.extend(rows.iter().map(|r| {
+        let text = editor::skip_columns(&content[r.clone()], hscroll);
+        if marked && cursor_line_bytes.contains(&r.start) {
+            Line::styled(text, Style::default().add_modifier(Modifier::UNDERLINED))
+        } else {
+            Line::from(text)
+        }
+    }));
+    f.render_widget(Paragraph::new(lines).scroll((scroll as u16, 0)), area);
+
+    if editing && area.width > 0 && area.height > 0 {
+        let x = (column - hscroll).min(area.width as usize - 1) as u16;
+        let y = (cursor_line - scroll) as u16;
+        f.set_cursor(area.x + x, area.y + y);
+    }
+}
+
+fn draw_catalog_section<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(list_and_editor(app).as_ref())
+        .split(area);
+
+    let order = app.catalog_display_order();
+    let items: Vec<ListItem> = order
+        .iter()
+        .map(|&i| &app.catalogs[i])
+        .map(|item| {
+            ListItem::new(with_tag_chips(
+                item.name.clone(),
+                &item.tags,
+                list_text_width(chunks[0]),
+            ))
+        })
+        .collect();
+
+    let block = Block::default().borders(Borders::ALL).title("Catalog");
+    if order.is_empty() {
+        let hint = if app.catalogs.is_empty() {
+            ["No bookmarks yet", "press : then nc <url> [name] to add one"]
+        } else {
+            ["No bookmarks match the tag filter", ":tagged with no tags clears it"]
+        };
+        draw_hint(f, app, block.inner(chunks[0]), &hint);
+    }
+    let list = List::new(items).block(block).highlight_style(app.theme.highlight);
+
+    let mut list_state = ListState::default()
+        .with_offset(app.catalogs_list_offset.get())
+        .with_selected(display_position(&order, app.selected_catalog));
+    f.render_stateful_widget(list, chunks[0], &mut list_state);
+    app.catalogs_list_offset.set(list_state.offset());
+
+    let detail_block = Block::default().borders(Borders::ALL).title("Details");
+    let inner_area = detail_block.inner(chunks[1]);
+    f.render_widget(detail_block, chunks[1]);
+
+    let shown = if matches!(app.mode, AppMode::Editing) {
+        app.current_catalog
+    } else {
+        app.selected_catalog
+    };
+    if let Some(item) = shown.and_then(|i| app.catalogs.get(i)) {
+        let header = vec![
+            Line::from(format!("Name: {}", item.name)),
+            Line::from(format!("URL: {}", item.url)),
+            tags_line(&item.tags),
+            Line::from(""),
+        ];
+        let open = shown.is_some() && shown == app.current_catalog;
+        draw_editor_body(f, app, inner_area, header, &item.description, true, open);
+    } else if !order.is_empty() {
+        draw_hint(f, app, inner_area, &["Nothing selected", "pick a bookmark with j/k"]);
     }
 }
 
 fn draw_todos_section<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+        .constraints(list_and_editor(app).as_ref())
         .split(area);
 
-    draw_todos_list(f, app, chunks[0]);
+    let list_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+        .split(chunks[0]);
+
+    draw_todos_list(f, app, list_chunks[0]);
+    draw_todos_progress(f, app, list_chunks[1]);
     draw_todo_editor(f, app, chunks[1]);
 }
 
+/// One-row gauge of how many live (unarchived) todos are completed.
+fn draw_todos_progress<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let live = app.todos.iter().filter(|t| !t.archived);
+    let (done, total) = live.fold((0, 0), |(done, total), todo| {
+        (done + todo.completed as usize, total + 1)
+    });
+    let ratio = if total == 0 { 0.0 } else { done as f64 / total as f64 };
+    let gauge = Gauge::default()
+        .gauge_style(Style::default().fg(app.theme.completed))
+        .ratio(ratio)
+        .label(format!("{}/{} done ({:.0}%)", done, total, ratio * 100.0));
+    f.render_widget(gauge, area);
+}
+
 fn draw_todos_list<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .todos
+    let order = app.todo_display_order();
+    let items: Vec<ListItem> = order
         .iter()
+        .map(|&i| &app.todos[i])
         .map(|todo| {
             let status = if todo.completed { "[✓]" } else { "[ ]" };
             let style = if todo.completed {
-                Style::default().fg(Color::Green)
+                Style::default().fg(app.theme.completed)
             } else if todo.is_overdue() {
-                Style::default().fg(Color::Red)
+                Style::default().fg(app.theme.overdue)
             } else {
                 Style::default()
             };
             let severity = match todo.severity {
-                crate::todo::Severity::Critical => "!!!",
-                crate::todo::Severity::High => "!!",
-                crate::todo::Severity::Medium => "!",
+                Severity::Critical => "!!!",
+                Severity::High => "!!",
+                Severity::Medium => "!",
                 _ => "",
             };
-            ListItem::new(format!("{} {} {}", status, severity, todo.title)).style(style)
+            // Completed todos keep their green row color; otherwise the marker
+            // shows the severity color and overdue rows stay red around it.
+            let marker_style = if todo.completed {
+                style
+            } else {
+                Style::default().fg(app.theme.severity_color(&todo.severity))
+            };
+            let prefix = format!("{}{} ", new_marker(&todo.created_at), status);
+            let marker = format!("{} ", severity);
+            let title_width =
+                list_text_width(area).saturating_sub(prefix.width() + marker.width());
+            let mut spans = vec![Span::raw(prefix), Span::styled(marker, marker_style)];
+            spans.extend(with_tag_chips(todo.title.clone(), &todo.tags, title_width).spans);
+            ListItem::new(Line::from(spans)).style(style)
         })
         .collect();
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Todos"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::Blue)
-                .add_modifier(Modifier::BOLD),
-        );
+    let block = Block::default().borders(Borders::ALL).title("Todos");
+    if order.is_empty() {
+        let hint = if app.show_archived {
+            ["No archived todos", ""]
+        } else if app.todos.iter().any(|t| !t.archived) {
+            ["No todos match the filter", ":filter all and :tagged clear filters"]
+        } else {
+            ["No todos yet", "press : then ntodo to create one"]
+        };
+        draw_hint(f, app, block.inner(area), &hint);
+    }
+    let list = List::new(items).block(block).highlight_style(app.theme.highlight);
 
-    let mut list_state = ListState::default();
-    list_state.select(app.selected_todo);
+    let mut list_state = ListState::default()
+        .with_offset(app.todos_list_offset.get())
+        .with_selected(display_position(&order, app.selected_todo));
     f.render_stateful_widget(list, area, &mut list_state);
+    app.todos_list_offset.set(list_state.offset());
 }
 
 fn draw_todo_editor<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
@@ -213,56 +890,212 @@ fn draw_todo_editor<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
         } else {
             "Pending"
         };
-        let due_date = todo
-            .due_date
-            .as_deref()
-            .map_or("Not set", |d| d);
-        let tags = if todo.tags.is_empty() {
-            "None".to_string()
+        let due_date = match (todo.due_date.as_deref(), todo.due_in_days()) {
+            (Some(date), Some(days)) => format!("{} ({})", date, dates::relative_days(days)),
+            (Some(date), None) => date.to_string(),
+            (None, _) => "Not set".to_string(),
+        };
+        let due_style = if todo.is_overdue() {
+            Style::default().fg(app.theme.overdue)
         } else {
-            todo.tags.join(", ")
+            Style::default()
         };
-        let header = format!(
-            "Title: {}\nCreated: {}\nStatus: {}\nDue: {}\nSeverity: {}\nTags: {}\n\n",
-            todo.title, todo.created_at, status, due_date, todo.severity, tags
-        );
-        let content = format!("{}{}", header, todo.content);
-        let editor_text = Paragraph::new(content);
-        f.render_widget(editor_text, inner_area);
+        let mut lines = vec![
+            Line::from(format!("Title: {}", todo.title)),
+            Line::from(format!("Created: {}", dates::friendly_timestamp(&todo.created_at))),
+            Line::from(format!("Modified: {}", dates::friendly_timestamp(&todo.updated_at))),
+            Line::from(format!("Status: {}", status)),
+            Line::from(vec![Span::raw("Due: "), Span::styled(due_date, due_style)]),
+            Line::from(vec![
+                Span::raw("Severity: "),
+                Span::styled(
+                    todo.severity.to_string(),
+                    Style::default().fg(app.theme.severity_color(&todo.severity)),
+                ),
+            ]),
+            tags_line(&todo.tags),
+        ];
+        if let Some(recurrence) = todo.recurrence {
+            lines.push(Line::from(format!("Repeats: {}", recurrence)));
+        }
+        if let Some(source) = todo
+            .source_note_id
+            .and_then(|id| app.notes.iter().find(|n| n.id == id))
+        {
+            lines.push(Line::from(format!("From note: {} (:source)", source.title)));
+        }
+        lines.push(Line::from(""));
+        draw_editor_body(f, app, inner_area, lines, &todo.content, true, true);
+    } else if !app.todo_display_order().is_empty() {
+        draw_hint(f, app, inner_area, &["Nothing open", "select a todo and press Enter"]);
     }
 }
 
 fn draw_help<B: Backend>(f: &mut Frame<B>, area: Rect) {
-    let help_text = "
+    let command_lines: Vec<String> = commands::help_lines()
+        .iter()
+        .map(|line| format!("      {}", line))
+        .collect();
+    let help_text = format!(
+        "
     Terminal Notes Help
     -------------------
 
     Navigation:
       j/Down - Move down in list
       k/Up   - Move up in list
+      gg/G   - Jump to the first/last item
       Enter  - Select item/Edit
       Esc    - Go back/Exit editing
+      dd     - Delete the open item's line under the cursor
+      Ctrl+N - New note, straight into editing (also from the menu)
+      Ctrl+H/Ctrl+L - Narrow/widen the list pane
+
+    Commands (press : to enter command mode, Up/Down for history,
+    Tab to complete):
+{}
+
+    Editing:
+      Arrows  - Move the cursor (Home/End: line start/end)
+      Ctrl+Left/Ctrl+Right - Jump to the previous/next word
+      Tab/Shift+Tab - Indent/dedent the current line
+      Ctrl+W  - Delete the word before the cursor
+      Ctrl+Z  - Undo, Ctrl+Y/Ctrl+R - Redo
+      Ctrl+D/Ctrl+T - Insert the current date/time
+      Ctrl+O  - Run one command, then keep editing
 
-    Commands (press : to enter command mode):
-      [n]nn    - Create [n] new notes
-      [n]ntodo - Create [n] new todos
-      [n]del   - Delete [n] items
-      :mm      - Go to main menu
-      :?       - Show this help
-      :save/:w - Save all data
-      :rnm     - Rename current note/todo
-      :backup  - Create a backup
-      :export-md - Export to Markdown
-      :export-csv - Export to CSV
-      :q/:quit - Quit application
-      :q!      - Force quit
+    Clipboard:
+      Y     - Copy the selected item's title
+      Ctrl+V/Ctrl+C - (Editing) Paste at the cursor/copy the whole text
 
     Todo Management:
       Space - Toggle todo completion
+      s     - Pick the selected todo's severity
+      +/-   - Raise/lower the selected todo's severity one level
 
-    Press Esc to exit this help screen.";
+    Press Esc to exit this help screen.",
+        command_lines.join("\n")
+    );
 
     let help_paragraph = Paragraph::new(help_text)
         .block(Block::default().borders(Borders::ALL).title("Help"));
     f.render_widget(help_paragraph, area);
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_io::{tests::temp_data_dir, FileIO};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn screen_rows(terminal: &Terminal<TestBackend>) -> Vec<String> {
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| (0..buffer.area.width).map(|x| buffer.get(x, y).symbol.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn selected_note_stays_visible_while_scrolling() {
+        let file_io = FileIO::open(temp_data_dir("list-offset")).unwrap();
+        let mut app = App::with_file_io(file_io).unwrap();
+        for _ in 0..100 {
+            app.command_buffer = "nn-blank".to_string();
+            app.execute_command();
+        }
+        app.mode = AppMode::Normal;
+        app.section = AppSection::Notes;
+        app.selected_note = Some(0);
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        for step in 1..100 {
+            app.next_note();
+            terminal.draw(|f| draw(f, &app)).unwrap();
+            let selected = format!(" Note {} ", step + 1);
+            assert!(
+                screen_rows(&terminal).iter().any(|row| row.contains(&selected)),
+                "{} scrolled off screen",
+                selected.trim()
+            );
+        }
+        assert!(app.notes_list_offset.get() > 0);
+    }
+
+    #[test]
+    fn normal_mode_underlines_the_line_dd_deletes() {
+        let file_io = FileIO::open(temp_data_dir("dd-marker")).unwrap();
+        let mut app = App::with_file_io(file_io).unwrap();
+        app.command_buffer = "nn".to_string();
+        app.execute_command();
+        app.notes[0].content = "alpha\nbravo\ncharlie".to_string();
+        app.cursor_pos = "alpha\nbr".len();
+        app.stop_editing();
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| draw(f, &app)).unwrap();
+        let rows = screen_rows(&terminal);
+        let underlined = |word: &str| {
+            let y = rows.iter().position(|row| row.contains(word)).unwrap();
+            // Border characters are multi-byte, so count columns, not bytes.
+            let x = rows[y][..rows[y].find(word).unwrap()].chars().count();
+            let buffer = terminal.backend().buffer();
+            buffer.get(x as u16, y as u16).modifier.contains(Modifier::UNDERLINED)
+        };
+        assert!(underlined("bravo"));
+        assert!(!underlined("alpha") && !underlined("charlie"));
+
+        app.delete_current_line();
+        assert_eq!(app.notes[0].content, "alpha\ncharlie");
+    }
+
+    #[test]
+    fn truncate_keeps_text_that_fits_exactly() {
+        assert_eq!(truncate_to_width("hello", 5), "hello");
+        assert_eq!(truncate_to_width("🎉🎉", 4), "🎉🎉");
+        assert_eq!(truncate_to_width("", 0), "");
+    }
+
+    #[test]
+    fn truncate_ends_with_an_ellipsis() {
+        assert_eq!(truncate_to_width("hello", 4), "hel…");
+        assert_eq!(truncate_to_width("🎉🎉🎉", 5), "🎉🎉…");
+        // A wide character that would straddle the limit is dropped whole.
+        assert_eq!(truncate_to_width("🎉🎉🎉", 4), "🎉…");
+    }
+
+    #[test]
+    fn truncate_handles_tiny_widths() {
+        assert_eq!(truncate_to_width("hello", 0), "");
+        assert_eq!(truncate_to_width("hello", 1), "…");
+        assert_eq!(truncate_to_width("hello", 2), "h…");
+        assert_eq!(truncate_to_width("🎉🎉", 1), "…");
+        assert_eq!(truncate_to_width("🎉🎉", 2), "…");
+        assert_eq!(truncate_to_width("日本語", 3), "日…");
+    }
+
+    #[test]
+    fn truncate_never_exceeds_the_width() {
+        let titles = ["📌 Pinned 🎉 party", "日本語のタイトル", "plain title", "a🎉b"];
+        for title in titles {
+            for width in 0..20 {
+                assert!(truncate_to_width(title, width).width() <= width, "{} at {}", title, width);
+            }
+        }
+    }
+
+    #[test]
+    fn tag_chips_fit_the_row() {
+        let tags = vec!["work".to_string(), "🎉".to_string(), "long-tag-name".to_string()];
+        for width in 0..40 {
+            let line = with_tag_chips("🎉 Party planning 日本".to_string(), &tags, width);
+            assert!(line.width() <= width, "line is {} wide at {}", line.width(), width);
+        }
+    }
+
+    #[test]
+    fn tag_chips_leave_half_the_row_for_text() {
+        let tags = vec!["verylongtag".to_string()];
+        let line = with_tag_chips("🎉🎉🎉🎉🎉🎉".to_string(), &tags, 16);
+        assert_eq!(line.spans[0].content, "🎉🎉🎉🎉🎉🎉");
+        assert_eq!(line.spans.len(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+// src/undo.rs
+use crate::{catalog::CatalogItem, note::Note, todo::Todo};
+use std::collections::VecDeque;
+
+/// Copy of every note, todo and catalog entry taken before a structural
+/// change (delete, sort, dedup, bulk rewrites).
+pub struct Snapshot {
+    pub notes: Vec<Note>,
+    pub todos: Vec<Todo>,
+    pub catalogs: Vec<CatalogItem>,
+}
+
+/// Bounded stack of snapshots; the oldest entry is dropped once full.
+pub struct History {
+    entries: VecDeque<Snapshot>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        History {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(snapshot);
+    }
+
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.entries.pop_back()
+    }
+}
+
+/// Kind of text edit, used to merge runs of the same edit into one step.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EditKind {
+    Insert,
+    Delete,
+    Other,
+}
+
+/// Content and cursor of the open item at one point in its edit history.
+pub struct TextState {
+    pub content: String,
+    pub cursor: usize,
+}
+
+/// Undo/redo stacks for the item open in the editor. Consecutive inserts
+/// (or deletes) share a single undo step until something else happens.
+pub struct TextHistory {
+    undo: Vec<TextState>,
+    redo: Vec<TextState>,
+    last_kind: Option<EditKind>,
+    capacity: usize,
+}
+
+impl TextHistory {
+    pub fn new(capacity: usize) -> Self {
+        TextHistory {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_kind: None,
+            capacity,
+        }
+    }
+
+    /// Records the state before an edit of `kind`, unless it continues the
+    /// current run of the same kind.
+    pub fn record(&mut self, before: TextState, kind: EditKind) {
+        let continues_run = kind != EditKind::Other && self.last_kind == Some(kind);
+        self.last_kind = Some(kind);
+        self.redo.clear();
+        if continues_run {
+            return;
+        }
+        if self.undo.len() == self.capacity {
+            self.undo.remove(0);
+        }
+        self.undo.push(before);
+    }
+
+    /// Ends the current run so the next edit starts a new undo step.
+    pub fn break_run(&mut self) {
+        self.last_kind = None;
+    }
+
+    pub fn undo(&mut self, current: TextState) -> Option<TextState> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        self.last_kind = None;
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: TextState) -> Option<TextState> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        self.last_kind = None;
+        Some(next)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_kind = None;
+    }
+}